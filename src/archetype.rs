@@ -0,0 +1,336 @@
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use handlebars::Handlebars;
+
+use crate::config::ResolvedConfig;
+
+/// Archetype used when the project defines neither a collection specific nor
+/// a default archetype
+const BUILTIN_ARCHETYPE: &str = "---\ntitle: \"{{title}}\"\ndate: {{date}}\n---\n\n";
+
+/// Values available as placeholders in archetypes
+pub struct ArchetypeData<'a> {
+    pub title: &'a str,
+    pub slug: &'a str,
+    pub date: &'a str,
+    pub collection: &'a str,
+}
+
+#[derive(Debug)]
+pub enum NewContentError {
+    AlreadyExists(PathBuf),
+    EmptySlug,
+    /// The collection name is not a single directory name, or no such
+    /// collection exists
+    InvalidCollection(String),
+    Io(io::Error),
+    Template(String),
+}
+
+impl std::fmt::Display for NewContentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NewContentError::AlreadyExists(path) => {
+                write!(f, "{} already exists", path.display())
+            }
+            NewContentError::EmptySlug => {
+                write!(f, "Title must contain at least one letter or digit")
+            }
+            NewContentError::InvalidCollection(collection) => write!(
+                f,
+                "Unknown collection {:?}, expected the name of a directory of the content directory or of a [collections] table",
+                collection
+            ),
+            NewContentError::Io(e) => write!(f, "IO error: {}", e),
+            NewContentError::Template(e) => write!(f, "Could not render archetype: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for NewContentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NewContentError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NewContentError {
+    fn from(err: io::Error) -> Self {
+        NewContentError::Io(err)
+    }
+}
+
+/// Turns a title into a file name and URL friendly slug
+///
+/// Letters and digits are lowercased and kept, every other run of characters
+/// becomes a single dash.
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    let mut pending_dash = false;
+
+    for c in title.chars() {
+        if c.is_alphanumeric() {
+            if pending_dash && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_dash = false;
+            slug.extend(c.to_lowercase());
+        } else {
+            pending_dash = true;
+        }
+    }
+
+    slug
+}
+
+/// Finds the archetype for `collection`, falling back to the default archetype
+/// of the project and then to the built-in one
+pub fn find_archetype(configuration: &ResolvedConfig, collection: &str) -> io::Result<String> {
+    let candidates = [
+        configuration
+            .archetypes_directory
+            .join(format!("{}.md", collection)),
+        configuration.archetypes_directory.join("default.md"),
+    ];
+
+    for candidate in candidates {
+        if fs::exists(&candidate)? {
+            log::debug!("Using archetype {}", candidate.display());
+            return fs::read_to_string(candidate);
+        }
+    }
+
+    log::debug!("No archetype found, using built-in archetype");
+    Ok(BUILTIN_ARCHETYPE.to_string())
+}
+
+/// Escapes `value` so that it can be placed inside a YAML double-quoted
+/// string, like `title: "{{title}}"`
+fn escape_yaml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Replaces the `{{title}}`, `{{slug}}`, `{{date}}` and `{{collection}}`
+/// placeholders of an archetype
+///
+/// Values are escaped for YAML double-quoted strings, triple braces like
+/// `{{{title}}}` insert them as is.
+pub fn render_archetype(archetype: &str, data: &ArchetypeData) -> Result<String, NewContentError> {
+    let mut registry = Handlebars::new();
+    registry.register_escape_fn(escape_yaml);
+
+    registry
+        .render_template(
+            archetype,
+            &serde_json::json!({
+                "title": data.title,
+                "slug": data.slug,
+                "date": data.date,
+                "collection": data.collection,
+            }),
+        )
+        .map_err(|e| NewContentError::Template(e.to_string()))
+}
+
+/// Returns true if `collection` is a plain directory name of an existing
+/// collection, one with a content directory, a details page or a
+/// `[collections.<name>]` table
+fn is_known_collection(configuration: &ResolvedConfig, collection: &str) -> io::Result<bool> {
+    let is_plain_name = matches!(
+        Path::new(collection).components().collect::<Vec<_>>().as_slice(),
+        [Component::Normal(name)] if *name == collection
+    );
+    if !is_plain_name {
+        return Ok(false);
+    }
+
+    Ok(configuration.collections.contains_key(collection)
+        || configuration.content_directory.join(collection).is_dir()
+        || fs::exists(configuration.details_page_path(collection))?)
+}
+
+/// Creates `content/<collection>/<slug>.md` from the collection archetype and
+/// returns its path
+pub fn create_content(
+    configuration: &ResolvedConfig,
+    collection: &str,
+    title: &str,
+    date: &str,
+) -> Result<PathBuf, NewContentError> {
+    if !is_known_collection(configuration, collection)? {
+        return Err(NewContentError::InvalidCollection(collection.to_string()));
+    }
+
+    let slug = slugify(title);
+    if slug.is_empty() {
+        return Err(NewContentError::EmptySlug);
+    }
+
+    let collection_directory = configuration.content_directory.join(collection);
+    let content_path = collection_directory.join(format!("{}.md", slug));
+    if fs::exists(&content_path)? {
+        return Err(NewContentError::AlreadyExists(content_path));
+    }
+
+    let details_page_path = configuration.details_page_path(collection);
+    if !fs::exists(&details_page_path)? {
+        log::warn!(
+            "Collection {} has no details page yet, create {} to render it",
+            collection,
+            details_page_path.display()
+        );
+    }
+
+    let archetype = find_archetype(configuration, collection)?;
+    let content = render_archetype(
+        &archetype,
+        &ArchetypeData {
+            title,
+            slug: &slug,
+            date,
+            collection,
+        },
+    )?;
+
+    fs::create_dir_all(&collection_directory)?;
+    fs::write(&content_path, content)?;
+
+    Ok(content_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello World"), "hello-world");
+        assert_eq!(slugify("  Rust: 2024 Edition!  "), "rust-2024-edition");
+        assert_eq!(slugify("Crème brûlée"), "crème-brûlée");
+        assert_eq!(slugify("--"), "");
+    }
+
+    #[test]
+    fn test_create_content_with_builtin_archetype() {
+        let temp_dir = TempDir::new().unwrap();
+        let configuration = Config::default().resolve(temp_dir.path());
+        fs::create_dir_all(configuration.content_directory.join("posts")).unwrap();
+
+        let path = create_content(&configuration, "posts", "Hello World", "2024-01-20").unwrap();
+
+        assert_eq!(
+            path,
+            configuration
+                .content_directory
+                .join("posts")
+                .join("hello-world.md")
+        );
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "---\ntitle: \"Hello World\"\ndate: 2024-01-20\n---\n\n"
+        );
+    }
+
+    #[test]
+    fn test_create_content_prefers_collection_archetype() {
+        let temp_dir = TempDir::new().unwrap();
+        let configuration = Config::default().resolve(temp_dir.path());
+        fs::create_dir_all(configuration.content_directory.join("posts")).unwrap();
+        fs::create_dir_all(configuration.pages_directory.join("notes")).unwrap();
+        fs::write(
+            configuration.pages_directory.join("notes/details.hbs"),
+            "{{fm.title}}",
+        )
+        .unwrap();
+        fs::create_dir_all(&configuration.archetypes_directory).unwrap();
+        fs::write(
+            configuration.archetypes_directory.join("default.md"),
+            "default",
+        )
+        .unwrap();
+        fs::write(
+            configuration.archetypes_directory.join("posts.md"),
+            "{{collection}}/{{slug}} {{title}} {{date}}",
+        )
+        .unwrap();
+
+        let post = create_content(&configuration, "posts", "First", "2024-01-20").unwrap();
+        assert_eq!(
+            fs::read_to_string(post).unwrap(),
+            "posts/first First 2024-01-20"
+        );
+
+        let note = create_content(&configuration, "notes", "First", "2024-01-20").unwrap();
+        assert_eq!(fs::read_to_string(note).unwrap(), "default");
+    }
+
+    #[test]
+    fn test_create_content_refuses_to_overwrite() {
+        let temp_dir = TempDir::new().unwrap();
+        let configuration = Config::default().resolve(temp_dir.path());
+        fs::create_dir_all(configuration.content_directory.join("posts")).unwrap();
+
+        create_content(&configuration, "posts", "Twice", "2024-01-20").unwrap();
+        let result = create_content(&configuration, "posts", "Twice", "2024-01-21");
+        assert!(matches!(result, Err(NewContentError::AlreadyExists(_))));
+    }
+
+    #[test]
+    fn test_create_content_rejects_unknown_collections() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config
+            .collections
+            .insert("docs".to_string(), Default::default());
+        let configuration = config.resolve(temp_dir.path());
+        fs::create_dir_all(configuration.content_directory.join("posts")).unwrap();
+
+        for collection in ["", "..", "../../escape", "posts/nested", "/tmp", "unknown"] {
+            let result = create_content(&configuration, collection, "Title", "2024-01-20");
+            assert!(
+                matches!(result, Err(NewContentError::InvalidCollection(_))),
+                "{:?} should be rejected",
+                collection
+            );
+        }
+        assert!(!temp_dir.path().join("escape").exists());
+
+        assert!(create_content(&configuration, "docs", "Title", "2024-01-20").is_ok());
+    }
+
+    #[test]
+    fn test_create_content_escapes_title() {
+        let temp_dir = TempDir::new().unwrap();
+        let configuration = Config::default().resolve(temp_dir.path());
+        fs::create_dir_all(configuration.content_directory.join("posts")).unwrap();
+
+        let title = r#"Say "hi" C:\temp now"#;
+        let path = create_content(&configuration, "posts", title, "2024-01-20").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "---\ntitle: \"Say \\\"hi\\\" C:\\\\temp now\"\ndate: 2024-01-20\n---\n\n"
+        );
+        let parsed = crate::collection::parse_markdown(&content).unwrap();
+        assert_eq!(parsed.fm["title"], title);
+    }
+}
@@ -0,0 +1,217 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+
+use crate::output;
+use crate::sorted_entries;
+
+/// Compiled patterns matched against the files of a directory that gets
+/// copied
+///
+/// Patterns without a `/` are matched against file and directory names, so
+/// that `*.map` matches source maps at any depth. Other patterns are matched
+/// against the path relative to the copied directory.
+#[derive(Debug, Clone, Default)]
+pub struct PathPatterns {
+    names: Vec<Pattern>,
+    paths: Vec<Pattern>,
+}
+
+impl PathPatterns {
+    pub fn new(patterns: &[String]) -> io::Result<Self> {
+        let mut compiled_patterns = Self::default();
+        for pattern in patterns {
+            let compiled = Pattern::new(pattern.trim_start_matches('/')).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid pattern {}: {}", pattern, e),
+                )
+            })?;
+            if pattern.contains('/') {
+                compiled_patterns.paths.push(compiled);
+            } else {
+                compiled_patterns.names.push(compiled);
+            }
+        }
+        Ok(compiled_patterns)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.paths.is_empty()
+    }
+
+    /// Returns true if one of the patterns matches `relative_path`
+    pub fn matches(&self, relative_path: &Path) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        let name_matches = relative_path.file_name().is_some_and(|name| {
+            self.names
+                .iter()
+                .any(|pattern| pattern.matches_with(&name.to_string_lossy(), options))
+        });
+        name_matches
+            || self
+                .paths
+                .iter()
+                .any(|pattern| pattern.matches_path_with(relative_path, options))
+    }
+}
+
+/// Decides which files of a directory get copied
+#[derive(Debug, Clone, Default)]
+pub struct CopyFilter {
+    /// Only files matching one of these patterns are copied, unless empty
+    pub include: PathPatterns,
+    /// Files and directories matching one of these patterns are skipped
+    pub exclude: PathPatterns,
+}
+
+impl CopyFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> io::Result<Self> {
+        Ok(Self {
+            include: PathPatterns::new(include)?,
+            exclude: PathPatterns::new(exclude)?,
+        })
+    }
+}
+
+/// A file of a copied directory, along with the path it is copied to
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopiedFile {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+}
+
+/// Copies the files of `from` into `to`, preserving the directory structure,
+/// and appends the path of every copied file to `copied`
+///
+/// Files whose content did not change are left untouched.
+pub fn copy_tree(
+    from: &Path,
+    to: &Path,
+    filter: &CopyFilter,
+    copied: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    copy_files(&list_tree(from, to, filter)?, copied)
+}
+
+/// Lists the files of `from` that [`copy_tree`] would copy into `to`
+pub fn list_tree(from: &Path, to: &Path, filter: &CopyFilter) -> io::Result<Vec<CopiedFile>> {
+    let mut files = Vec::new();
+    list_directory(from, from, to, filter, &mut files)?;
+    Ok(files)
+}
+
+/// Copies `files` and appends the path of every copied file to `copied`
+///
+/// Files whose content did not change are left untouched.
+pub fn copy_files(files: &[CopiedFile], copied: &mut Vec<PathBuf>) -> io::Result<()> {
+    for file in files {
+        if let Some(parent) = file.destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        log::debug!(
+            "Copying {} to {}",
+            file.source.display(),
+            file.destination.display()
+        );
+        output::copy_if_changed(&file.source, &file.destination)?;
+        copied.push(file.destination.clone());
+    }
+    Ok(())
+}
+
+fn list_directory(
+    root: &Path,
+    directory: &Path,
+    to: &Path,
+    filter: &CopyFilter,
+    files: &mut Vec<CopiedFile>,
+) -> io::Result<()> {
+    for entry in sorted_entries(directory)? {
+        let path = entry.path();
+        let relative_path = path
+            .strip_prefix(root)
+            .expect("Entry should be inside the copied directory");
+        if filter.exclude.matches(relative_path) {
+            log::debug!("Excluding {}", path.display());
+            continue;
+        }
+
+        if entry.metadata()?.is_dir() {
+            list_directory(root, &path, to, filter, files)?;
+            continue;
+        }
+
+        if !filter.include.is_empty() && !filter.include.matches(relative_path) {
+            log::debug!("{} is not included, skipping", path.display());
+            continue;
+        }
+
+        files.push(CopiedFile {
+            destination: to.join(relative_path),
+            source: path,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_path_patterns() {
+        let patterns = PathPatterns::new(&[
+            ".DS_Store".to_string(),
+            "*.map".to_string(),
+            "/drafts/*.png".to_string(),
+        ])
+        .unwrap();
+
+        assert!(patterns.matches(Path::new(".DS_Store")));
+        assert!(patterns.matches(Path::new("img/.DS_Store")));
+        assert!(patterns.matches(Path::new("js/app.js.map")));
+        assert!(patterns.matches(Path::new("drafts/logo.png")));
+        assert!(!patterns.matches(Path::new("img/drafts/logo.png")));
+        assert!(!patterns.matches(Path::new("js/app.js")));
+
+        assert!(PathPatterns::new(&["[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_copy_tree_preserves_structure() {
+        let temp_dir = TempDir::new().unwrap();
+        let from = temp_dir.path().join("assets");
+        let to = temp_dir.path().join("dist");
+        fs::create_dir_all(from.join("img/icons")).unwrap();
+        fs::write(from.join("favicon.ico"), "icon").unwrap();
+        fs::write(from.join("img/icons/logo.png"), "logo").unwrap();
+        fs::write(from.join("img/.DS_Store"), "").unwrap();
+        fs::write(from.join("img/notes.txt"), "notes").unwrap();
+
+        let filter = CopyFilter::new(
+            &["*.ico".to_string(), "*.png".to_string()],
+            &[".DS_Store".to_string()],
+        )
+        .unwrap();
+        let mut copied = Vec::new();
+        copy_tree(&from, &to, &filter, &mut copied).unwrap();
+
+        assert_eq!(
+            copied,
+            vec![to.join("favicon.ico"), to.join("img/icons/logo.png")]
+        );
+        assert_eq!(
+            fs::read_to_string(to.join("img/icons/logo.png")).unwrap(),
+            "logo"
+        );
+        assert!(!to.join("img/.DS_Store").exists());
+        assert!(!to.join("img/notes.txt").exists());
+    }
+}
@@ -0,0 +1,362 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+use crate::assets;
+use crate::cache::{self, BuildCache};
+use crate::config::{Config, ConfigError};
+use crate::hooks::{HookExecutor, HookPhase, HookRun};
+use crate::output::{self, OutputManifest};
+use crate::renderer::{HandlebarsRenderer, Renderer};
+use crate::report::{Diagnostics, PhaseTiming, RenderedPage};
+use crate::routes;
+use crate::sitemap::SitePages;
+use crate::{
+    discover_collections_with_diagnostics, discover_static_pages, discover_taxonomies,
+    make_dist_folder, make_dist_folder_unchecked, render_collection_items, render_collection_lists,
+    render_pages, render_taxonomies, static_files, write_sitemap,
+};
+
+/// Error returned when a build fails, tagged with the phase that failed
+#[derive(Debug)]
+pub enum BuildError {
+    Config(ConfigError),
+    Hook(io::Error),
+    Render(io::Error),
+    Output(io::Error),
+    Discovery(io::Error),
+    Sitemap(io::Error),
+    Asset(io::Error),
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::Config(e) => write!(f, "{}", e),
+            BuildError::Hook(e) => write!(f, "{}", e),
+            BuildError::Render(e) => write!(f, "Error rendering: {}", e),
+            BuildError::Output(e) => write!(f, "Error writing output directory: {}", e),
+            BuildError::Discovery(e) => write!(f, "Error discovering pages: {}", e),
+            BuildError::Sitemap(e) => write!(f, "Error writing sitemap: {}", e),
+            BuildError::Asset(e) => write!(f, "Error handling assets: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildError::Config(e) => Some(e),
+            BuildError::Hook(e)
+            | BuildError::Render(e)
+            | BuildError::Output(e)
+            | BuildError::Discovery(e)
+            | BuildError::Sitemap(e)
+            | BuildError::Asset(e) => Some(e),
+        }
+    }
+}
+
+impl From<ConfigError> for BuildError {
+    fn from(err: ConfigError) -> Self {
+        BuildError::Config(err)
+    }
+}
+
+/// Summary of a successful build
+#[derive(Debug)]
+pub struct BuildReport {
+    /// Every page discovered during the build
+    pub site_pages: SitePages,
+    /// Number of pages that were rendered, the others being up to date
+    pub rendered_pages: usize,
+    /// Asset files copied to the output directory
+    pub assets: Vec<PathBuf>,
+    /// Outputs of a previous build that were removed
+    pub removed_outputs: Vec<PathBuf>,
+    pub duration: Duration,
+    /// Time spent in each phase, in execution order
+    pub phases: Vec<PhaseTiming>,
+    /// Hooks that ran, in execution order
+    pub hooks: Vec<HookRun>,
+    /// Pages written by this build, with their render time
+    pub rendered: Vec<RenderedPage>,
+    pub diagnostics: Diagnostics,
+}
+
+/// Builds a balzac project
+///
+/// ```no_run
+/// use balzac::build::Builder;
+///
+/// let report = Builder::from_root("./my-site")?.clean(true).build()?;
+/// println!("Built {} pages", report.site_pages.all().len());
+/// # Ok::<(), balzac::build::BuildError>(())
+/// ```
+pub struct Builder {
+    config: Config,
+    root: PathBuf,
+    clean: bool,
+    force: bool,
+}
+
+impl Builder {
+    /// Creates a builder for `config`, resolving relative paths against `root`
+    pub fn new(config: Config, root: impl Into<PathBuf>) -> Self {
+        Self {
+            config,
+            root: root.into(),
+            clean: false,
+            force: false,
+        }
+    }
+
+    /// Creates a builder from the balzac.toml file found in `root`
+    pub fn from_root(root: impl Into<PathBuf>) -> Result<Self, BuildError> {
+        Self::from_root_with_environment(root, None)
+    }
+
+    /// Creates a builder from the balzac.toml file found in `root`, merged
+    /// with the overlay file of `environment` if one is given
+    pub fn from_root_with_environment(
+        root: impl Into<PathBuf>,
+        environment: Option<&str>,
+    ) -> Result<Self, BuildError> {
+        let root = root.into();
+        let config = Config::load_with_environment(&root, environment)?;
+        Ok(Self::new(config, root))
+    }
+
+    /// Ignores the build cache and rebuilds the output directory from scratch
+    pub fn clean(mut self, clean: bool) -> Self {
+        self.clean = clean;
+        self
+    }
+
+    /// Cleans the output directory even if it is not safe to delete, see
+    /// [`output::ensure_safe_to_clean`]
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Builds collection items marked as drafts, overriding the `drafts`
+    /// setting of the configuration
+    pub fn drafts(mut self, drafts: bool) -> Self {
+        self.config.drafts = drafts;
+        self
+    }
+
+    /// Checks the publication window of collection items against `now`
+    /// instead of the time of the build
+    pub fn now(mut self, now: DateTime<Utc>) -> Self {
+        self.config.now = Some(now);
+        self
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn build(&self) -> Result<BuildReport, BuildError> {
+        let build_start = Instant::now();
+        self.config.validate()?;
+        let resolved_config = self.config.resolve(&self.root);
+        let mut phases = Vec::new();
+        let mut diagnostics = Diagnostics::default();
+
+        let hook_executor = HookExecutor::new(self.config.hooks.as_ref(), &self.root);
+        let mut hooks = Vec::new();
+        let mut run_hook = |phase| -> Result<(), BuildError> {
+            if let Some(run) = hook_executor.execute(phase).map_err(BuildError::Hook)? {
+                hooks.push(run);
+            }
+            Ok(())
+        };
+
+        run_hook(HookPhase::RenderInitBefore)?;
+
+        let start = Instant::now();
+        let mut render: HandlebarsRenderer<'_> = HandlebarsRenderer::new(&resolved_config);
+        render.init(&resolved_config).map_err(BuildError::Render)?;
+        let duration = record_phase(&mut phases, "renderer_init", start);
+        log::info!("Renderer is initialized (took {:?})", duration);
+
+        run_hook(HookPhase::RenderInitAfter)?;
+        run_hook(HookPhase::BuildBefore)?;
+
+        let global_fingerprint = cache::global_fingerprint(&self.config, &resolved_config)
+            .map_err(BuildError::Output)?;
+        let mut build_cache = BuildCache::new(global_fingerprint);
+        let previous_cache = if self.clean {
+            None
+        } else {
+            BuildCache::load(&resolved_config)
+        };
+
+        let start = Instant::now();
+        if self.clean && self.force {
+            make_dist_folder_unchecked(&resolved_config).map_err(BuildError::Output)?;
+        } else if self.clean {
+            make_dist_folder(&resolved_config).map_err(BuildError::Output)?;
+        } else {
+            fs::create_dir_all(&resolved_config.output_directory).map_err(BuildError::Output)?;
+        }
+        let previous_manifest = if self.clean {
+            None
+        } else {
+            OutputManifest::load(&resolved_config)
+        };
+        let mut manifest = OutputManifest::default();
+        let duration = record_phase(&mut phases, "output_directory", start);
+        log::info!("Created output directory (took {:?})", duration);
+
+        let start = Instant::now();
+        let static_pages =
+            discover_static_pages(&resolved_config).map_err(BuildError::Discovery)?;
+        let collection_pages =
+            discover_collections_with_diagnostics(&resolved_config, &mut diagnostics)
+                .map_err(BuildError::Discovery)?;
+        let taxonomy_pages = discover_taxonomies(&resolved_config, &collection_pages)
+            .map_err(BuildError::Discovery)?;
+
+        let mut site_pages = SitePages::new();
+        site_pages.add_pages(static_pages);
+        site_pages.add_pages(collection_pages);
+        site_pages.add_pages(taxonomy_pages);
+
+        // Copied files are listed up front, so that a file mounted over a
+        // page fails the build rather than silently replacing it
+        let static_files = static_files(&resolved_config).map_err(BuildError::Asset)?;
+        let conflicts = routes::find_conflicts(site_pages.all(), &static_files);
+        if !conflicts.is_empty() {
+            let conflicts: Vec<String> = conflicts.iter().map(ToString::to_string).collect();
+            return Err(BuildError::Discovery(io::Error::new(
+                io::ErrorKind::InvalidData,
+                conflicts.join("; "),
+            )));
+        }
+        let duration = record_phase(&mut phases, "discovery", start);
+        log::info!(
+            "Discovered {} pages (took {:?})",
+            site_pages.all().len(),
+            duration
+        );
+
+        let changed_pages = build_cache
+            .record_pages(&resolved_config, site_pages.all(), previous_cache.as_ref())
+            .map_err(BuildError::Discovery)?;
+        log::info!(
+            "{} of {} pages changed since last build",
+            changed_pages.len(),
+            site_pages.all().len()
+        );
+        // Changed pages are a subset of the discovered pages
+        diagnostics.unchanged = site_pages.all().len() - changed_pages.len();
+
+        run_hook(HookPhase::RenderBefore)?;
+
+        let start = Instant::now();
+        let mut rendered =
+            render_pages(&resolved_config, &changed_pages, &render).map_err(BuildError::Render)?;
+        let duration = record_phase(&mut phases, "static_pages", start);
+        log::info!("Rendered static pages (took {:?})", duration);
+
+        let start = Instant::now();
+        rendered.extend(
+            render_collection_items(&resolved_config, &changed_pages, &site_pages, &render)
+                .map_err(BuildError::Render)?,
+        );
+        rendered.extend(
+            render_collection_lists(&resolved_config, &changed_pages, &site_pages, &render)
+                .map_err(BuildError::Render)?,
+        );
+        let duration = record_phase(&mut phases, "collections", start);
+        log::info!("Rendered collections (took {:?})", duration);
+
+        let start = Instant::now();
+        rendered.extend(
+            render_taxonomies(&resolved_config, &changed_pages, &site_pages, &render)
+                .map_err(BuildError::Render)?,
+        );
+        let duration = record_phase(&mut phases, "taxonomies", start);
+        log::info!("Rendered taxonomies (took {:?})", duration);
+
+        run_hook(HookPhase::RenderAfter)?;
+
+        let start = Instant::now();
+        let sitemap_path =
+            write_sitemap(&resolved_config, &site_pages).map_err(BuildError::Sitemap)?;
+        let duration = record_phase(&mut phases, "sitemap", start);
+        if resolved_config.base_url.is_some() {
+            log::info!("Generated sitemap (took {:?})", duration);
+        }
+
+        let start = Instant::now();
+        let mut assets = Vec::new();
+        assets::copy_files(&static_files, &mut assets).map_err(BuildError::Asset)?;
+        let duration = record_phase(&mut phases, "assets", start);
+        log::info!("Handled assets (took {:?})", duration);
+
+        let start = Instant::now();
+        let outputs = site_pages
+            .all()
+            .iter()
+            .map(|page| &page.output_path)
+            .chain(&sitemap_path)
+            .chain(&assets);
+        for output_path in outputs {
+            manifest.record(&resolved_config, output_path);
+        }
+
+        let mut removed_outputs = Vec::new();
+        if let Some(previous_manifest) = &previous_manifest {
+            removed_outputs = previous_manifest.stale_outputs(&resolved_config, &manifest);
+            output::remove_stale_outputs(&resolved_config, &removed_outputs)
+                .map_err(BuildError::Output)?;
+            if !removed_outputs.is_empty() {
+                log::info!("Removed {} stale output(s)", removed_outputs.len());
+            }
+        }
+        manifest
+            .save(&resolved_config)
+            .map_err(BuildError::Output)?;
+        build_cache
+            .save(&resolved_config)
+            .map_err(BuildError::Output)?;
+        record_phase(&mut phases, "cleanup", start);
+
+        run_hook(HookPhase::BuildAfter)?;
+
+        Ok(BuildReport {
+            site_pages,
+            rendered_pages: changed_pages.len(),
+            assets,
+            removed_outputs,
+            duration: build_start.elapsed(),
+            phases,
+            hooks,
+            rendered,
+            diagnostics,
+        })
+    }
+}
+
+/// Records the time elapsed since `start` as phase `name` and returns it
+fn record_phase(phases: &mut Vec<PhaseTiming>, name: &'static str, start: Instant) -> Duration {
+    let duration = start.elapsed();
+    phases.push(PhaseTiming { name, duration });
+    duration
+}
+
+/// Builds `config`, resolving relative paths against `root`
+pub fn build(config: Config, root: &Path) -> Result<BuildReport, BuildError> {
+    Builder::new(config, root).build()
+}
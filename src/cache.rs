@@ -0,0 +1,263 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::{Config, ResolvedConfig};
+use crate::output::output_key;
+use crate::sitemap::{PageEntry, PageType};
+
+/// Name of the cache file, stored in the project root
+pub const CACHE_FILENAME: &str = ".balzac-cache.json";
+
+/// Fingerprints of the inputs used by the previous build, used to skip
+/// rendering pages whose inputs did not change
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BuildCache {
+    /// Fingerprint of the inputs shared by every page (config, partials,
+    /// layouts, vite manifest and balzac version)
+    pub global: String,
+    /// Page outputs, relative to the output directory, mapped to the
+    /// fingerprint of the inputs they were rendered from
+    pub pages: BTreeMap<String, String>,
+}
+
+impl BuildCache {
+    pub fn new(global: String) -> Self {
+        Self {
+            global,
+            ..Default::default()
+        }
+    }
+
+    /// Loads the cache of the previous build, if there is a usable one
+    pub fn load(configuration: &ResolvedConfig) -> Option<Self> {
+        let path = cache_path(configuration);
+        let content = fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                log::warn!("Ignoring unreadable build cache {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, configuration: &ResolvedConfig) -> io::Result<()> {
+        let content = serde_json::to_string(self).map_err(io::Error::other)?;
+        fs::write(cache_path(configuration), content)
+    }
+
+    /// Returns true if `page` was rendered from the same inputs by the
+    /// previous build and its output still exists
+    pub fn is_fresh(
+        &self,
+        configuration: &ResolvedConfig,
+        page: &PageEntry,
+        fingerprint: &str,
+    ) -> bool {
+        output_key(configuration, &page.output_path)
+            .and_then(|key| self.pages.get(&key))
+            .is_some_and(|f| f == fingerprint)
+            && page.output_path.exists()
+    }
+
+    pub fn record_page(
+        &mut self,
+        configuration: &ResolvedConfig,
+        page: &PageEntry,
+        fingerprint: String,
+    ) {
+        if let Some(key) = output_key(configuration, &page.output_path) {
+            self.pages.insert(key, fingerprint);
+        }
+    }
+
+    /// Records the fingerprint of every page and returns the pages that need
+    /// to be rendered again compared to the `previous` build
+    ///
+    /// Every page is considered changed when there is no previous build or
+    /// when an input shared by all pages changed.
+    pub fn record_pages(
+        &mut self,
+        configuration: &ResolvedConfig,
+        pages: &[PageEntry],
+        previous: Option<&BuildCache>,
+    ) -> io::Result<Vec<PageEntry>> {
+        let previous = previous.filter(|cache| cache.global == self.global);
+        let mut changed = Vec::new();
+
+        // Collection lists, and items of collections with a section tree,
+        // show their whole collection, so they are rendered again whenever an
+        // item of the collection is added, moved, removed or edited. Only the
+        // fields the tree and lists expose are hashed.
+        let mut listings: BTreeMap<&str, Sha256> = BTreeMap::new();
+        for page in pages {
+            if let PageType::Collection { name } = &page.page_type {
+                let hasher = listings.entry(name.as_str()).or_default();
+                hasher.update(page.url_path.as_bytes());
+                hasher.update(page.sections.join("/").as_bytes());
+                if let Some(frontmatter) = &page.frontmatter {
+                    hasher.update(frontmatter.to_string());
+                }
+            }
+        }
+        let listings: BTreeMap<&str, String> = listings
+            .into_iter()
+            .map(|(name, hasher)| (name, format!("{:x}", hasher.finalize())))
+            .collect();
+
+        // Taxonomy pages may list items of any collection
+        let mut site_listing = Sha256::new();
+        for listing in listings.values() {
+            site_listing.update(listing);
+        }
+        let site_listing = format!("{:x}", site_listing.finalize());
+
+        for page in pages {
+            let listing = match &page.page_type {
+                PageType::TaxonomyList { .. } | PageType::TaxonomyTerm { .. } => {
+                    Some(site_listing.as_str())
+                }
+                PageType::Collection { name } if !configuration.collection(name).tree => None,
+                page_type => page_type
+                    .collection_name()
+                    .and_then(|name| listings.get(name))
+                    .map(String::as_str),
+            };
+            let fingerprint = page_fingerprint(configuration, page, listing)?;
+            if !previous.is_some_and(|cache| cache.is_fresh(configuration, page, &fingerprint)) {
+                changed.push(page.clone());
+            }
+            self.record_page(configuration, page, fingerprint);
+        }
+
+        Ok(changed)
+    }
+}
+
+/// Fingerprints every input that affects all pages at once
+pub fn global_fingerprint(config: &Config, configuration: &ResolvedConfig) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+
+    let serialized_config = toml::to_string(config).map_err(io::Error::other)?;
+    hasher.update(serialized_config);
+    hasher.update(config.environment.as_deref().unwrap_or_default());
+
+    hash_directory(&mut hasher, &configuration.partials_directory)?;
+    hash_directory(&mut hasher, &configuration.layouts_directory)?;
+
+    if let Some(bundler) = &configuration.bundler
+        && let Some(vite) = &bundler.vite
+        && vite.enabled
+    {
+        let manifest_path = configuration.root_directory.join(&vite.manifest_path);
+        if fs::exists(&manifest_path)? {
+            hasher.update(fs::read(manifest_path)?);
+        }
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Fingerprints the files a single page is rendered from, along with the
+/// `listing` fingerprint of its collection
+pub fn page_fingerprint(
+    configuration: &ResolvedConfig,
+    page: &PageEntry,
+    listing: Option<&str>,
+) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(fs::read(&page.source_path)?);
+    if let Some(listing) = listing {
+        hasher.update(listing);
+    }
+
+    if let PageType::Collection { name } = &page.page_type {
+        let details_page_path = configuration.details_page_path(name);
+        hasher.update(fs::read(details_page_path)?);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn hash_directory(hasher: &mut Sha256, directory: &Path) -> io::Result<()> {
+    if !fs::exists(directory)? {
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(directory)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_file() {
+            hasher.update(path.to_string_lossy().as_bytes());
+            hasher.update(fs::read(&path)?);
+        }
+    }
+    Ok(())
+}
+
+fn cache_path(configuration: &ResolvedConfig) -> PathBuf {
+    configuration.root_directory.join(CACHE_FILENAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sitemap::SitemapMeta;
+    use tempfile::TempDir;
+
+    fn page(configuration: &ResolvedConfig, name: &str) -> PageEntry {
+        PageEntry {
+            url_path: format!("/{}", name),
+            source_path: configuration.pages_directory.join(format!("{}.hbs", name)),
+            output_path: configuration
+                .output_directory
+                .join(format!("{}.html", name)),
+            page_type: PageType::Static,
+            sitemap_meta: SitemapMeta::default(),
+            frontmatter: None,
+            content: None,
+            draft: false,
+            sections: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_page_is_fresh_only_with_same_fingerprint_and_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let configuration = Config::default().resolve(temp_dir.path());
+        fs::create_dir(&configuration.output_directory).unwrap();
+        let about = page(&configuration, "about");
+
+        let mut cache = BuildCache::new("global".to_string());
+        cache.record_page(&configuration, &about, "abc".to_string());
+        assert!(!cache.is_fresh(&configuration, &about, "abc"));
+
+        fs::write(&about.output_path, "about").unwrap();
+        assert!(cache.is_fresh(&configuration, &about, "abc"));
+        assert!(!cache.is_fresh(&configuration, &about, "def"));
+    }
+
+    #[test]
+    fn test_global_fingerprint_changes_with_partials() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::default();
+        let configuration = config.resolve(temp_dir.path());
+        fs::create_dir(&configuration.partials_directory).unwrap();
+        fs::write(configuration.partials_directory.join("nav.hbs"), "<nav>").unwrap();
+
+        let before = global_fingerprint(&config, &configuration).unwrap();
+        assert_eq!(before, global_fingerprint(&config, &configuration).unwrap());
+
+        fs::write(configuration.partials_directory.join("nav.hbs"), "<nav />").unwrap();
+        assert_ne!(before, global_fingerprint(&config, &configuration).unwrap());
+    }
+}
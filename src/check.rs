@@ -0,0 +1,573 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use handlebars::template::{HelperTemplate, Parameter, Template, TemplateElement};
+use serde_json::Value;
+
+use crate::assets::{CopyFilter, PathPatterns};
+use crate::collection;
+use crate::config::{Config, ResolvedConfig};
+use crate::date;
+use crate::permalink::{self, PermalinkFields};
+use crate::routes::Routes;
+use crate::taxonomy;
+use crate::vite::{ViteManifest, get_file, parse_manifest};
+use crate::{discover_static_pages, item_sections, sorted_entries};
+
+/// A problem found while checking a project
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+/// Validates the project found in `root` without writing any output, using
+/// the overlay file of `environment` if one is given
+///
+/// Every problem found is returned instead of stopping at the first one.
+pub fn check(root: &Path, environment: Option<&str>) -> Vec<Problem> {
+    match Config::load_with_environment(root, environment) {
+        Ok(config) => check_config(&config, root),
+        Err(e) => vec![Problem {
+            path: root.join(e.filename()),
+            message: e.to_string(),
+        }],
+    }
+}
+
+/// Validates `config`, resolving relative paths against `root`
+pub fn check_config(config: &Config, root: &Path) -> Vec<Problem> {
+    if let Err(e) = config.validate() {
+        return vec![Problem {
+            path: root.join(e.filename()),
+            message: e.to_string(),
+        }];
+    }
+    let configuration = config.resolve(root);
+    let mut checker = Checker {
+        configuration: &configuration,
+        problems: Vec::new(),
+        partials: HashSet::new(),
+        templates: Vec::new(),
+    };
+
+    checker.check_partials(&configuration.partials_directory);
+    checker.check_partials(&configuration.layouts_directory);
+    checker.check_pages();
+    checker.check_collections();
+    checker.check_taxonomies();
+    checker.check_static_files();
+    checker.check_routes();
+
+    let manifest = checker.check_vite_manifest();
+    checker.check_references(manifest.as_ref());
+
+    checker.problems
+}
+
+struct Checker<'a> {
+    configuration: &'a ResolvedConfig,
+    problems: Vec<Problem>,
+    /// Names of every registered partial and layout
+    partials: HashSet<String>,
+    /// Every successfully compiled template, along with its source file
+    templates: Vec<(PathBuf, Template)>,
+}
+
+impl<'a> Checker<'a> {
+    fn report(&mut self, path: &Path, message: impl Into<String>) {
+        self.problems.push(Problem {
+            path: path.to_path_buf(),
+            message: message.into(),
+        });
+    }
+
+    fn check_partials(&mut self, directory: &Path) {
+        if !directory.exists() {
+            return;
+        }
+
+        let entries = match sorted_entries(directory) {
+            Ok(entries) => entries,
+            Err(e) => return self.report(directory, e.to_string()),
+        };
+
+        for entry in entries {
+            let path = entry.path();
+            if let Some(name) = path.file_stem() {
+                self.partials.insert(name.to_string_lossy().to_string());
+            }
+            self.compile(&path);
+        }
+    }
+
+    fn check_pages(&mut self) {
+        match discover_static_pages(self.configuration) {
+            Ok(pages) => {
+                for page in pages {
+                    self.compile(&page.source_path);
+                }
+            }
+            Err(e) => {
+                let pages_directory = self.configuration.pages_directory.clone();
+                self.report(&pages_directory, e.to_string());
+            }
+        }
+    }
+
+    fn check_collections(&mut self) {
+        let content_directory = self.configuration.content_directory.clone();
+        if !content_directory.exists() {
+            return;
+        }
+
+        let entries = match sorted_entries(&content_directory) {
+            Ok(entries) => entries,
+            Err(e) => return self.report(&content_directory, e.to_string()),
+        };
+
+        for entry in entries {
+            let path = entry.path();
+            if !path.is_dir() {
+                self.report(
+                    &path,
+                    "files are not allowed at the top level of the content directory",
+                );
+                continue;
+            }
+
+            let collection_name = entry.file_name().to_string_lossy().to_string();
+            let details_page_path = self.configuration.details_page_path(&collection_name);
+            if details_page_path.is_file() {
+                self.compile(&details_page_path);
+            } else {
+                self.report(
+                    &path,
+                    format!(
+                        "collection {} has no details page, expected {}",
+                        collection_name,
+                        details_page_path.display()
+                    ),
+                );
+            }
+
+            let list_page_path = self.configuration.list_page_path(&collection_name);
+            if list_page_path.is_file() {
+                self.compile(&list_page_path);
+            }
+            if self.configuration.collection(&collection_name).paginate == Some(0) {
+                self.report(
+                    &self.configuration.root_directory.join("balzac.toml"),
+                    format!("collection {} cannot paginate by 0 items", collection_name),
+                );
+            }
+
+            self.check_collection_items(&collection_name, &path);
+        }
+    }
+
+    fn check_taxonomies(&mut self) {
+        let configuration = self.configuration;
+        let config_path = configuration.root_directory.join("balzac.toml");
+        for (name, taxonomy) in &configuration.taxonomies {
+            for template in [&taxonomy.list, &taxonomy.term] {
+                if template.is_file() {
+                    self.compile(template);
+                }
+            }
+            for collection in &taxonomy.collections {
+                if !configuration.content_directory.join(collection).is_dir() {
+                    self.report(
+                        &config_path,
+                        format!(
+                            "taxonomy {} classifies unknown collection {}",
+                            name, collection
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_collection_items(&mut self, collection_name: &str, directory: &Path) {
+        let entries = match sorted_entries(directory) {
+            Ok(entries) => entries,
+            Err(e) => return self.report(directory, e.to_string()),
+        };
+
+        for entry in entries {
+            let path = entry.path();
+            if path.is_dir() {
+                self.check_collection_items(collection_name, &path);
+                continue;
+            }
+            if path.extension().is_none_or(|e| e != "md") {
+                continue;
+            }
+
+            let result =
+                fs::read_to_string(&path).and_then(|content| collection::parse_markdown(&content));
+            match result {
+                Ok(parsed) => {
+                    for key in date::SCHEDULE_KEYS {
+                        if let Err(e) = date::frontmatter_date(&parsed.fm, key) {
+                            self.report(&path, e);
+                        }
+                    }
+                    self.check_item_location(collection_name, &path, &parsed.fm);
+                    let configuration = self.configuration;
+                    for (taxonomy_name, taxonomy) in &configuration.taxonomies {
+                        if !taxonomy.classifies(collection_name) {
+                            continue;
+                        }
+                        if let Err(e) = taxonomy::item_terms(&parsed.fm, &taxonomy.field) {
+                            self.report(&path, format!("taxonomy {}: {}", taxonomy_name, e));
+                        }
+                    }
+                }
+                Err(e) => self.report(&path, e.to_string()),
+            }
+        }
+    }
+
+    /// Reports `slug` and `url` frontmatter fields that are invalid, and
+    /// items whose permalink cannot be built
+    fn check_item_location(&mut self, collection_name: &str, path: &Path, frontmatter: &Value) {
+        let file_stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let slug = match permalink::item_slug(frontmatter, &file_stem) {
+            Ok(slug) => slug,
+            Err(e) => return self.report(path, e),
+        };
+        match permalink::item_url(frontmatter) {
+            Ok(Some(_)) => return,
+            Ok(None) => {}
+            Err(e) => return self.report(path, e),
+        }
+
+        let Some(pattern) = self.configuration.collection(collection_name).permalink else {
+            return;
+        };
+        let sections = item_sections(self.configuration, collection_name, path);
+        let fields = PermalinkFields {
+            collection: collection_name,
+            slug: &slug,
+            sections: &sections,
+            frontmatter,
+        };
+        if let Err(e) = permalink::expand(&pattern, &fields) {
+            self.report(path, format!("invalid permalink: {}", e));
+        }
+    }
+
+    /// Reports pages that share a URL or an output file
+    fn check_routes(&mut self) {
+        // Items and static directories that cannot be discovered are left
+        // out, they are reported by the other checks
+        let Ok(routes) = Routes::discover_valid(self.configuration) else {
+            return;
+        };
+        for conflict in routes.conflicts {
+            self.report(&conflict.sources[0], conflict.to_string());
+        }
+    }
+
+    fn check_static_files(&mut self) {
+        let config_path = self.configuration.root_directory.join("balzac.toml");
+        if let Err(e) = PathPatterns::new(&self.configuration.assets_ignore) {
+            self.report(&config_path, format!("assets_ignore: {}", e));
+        }
+
+        for mount in &self.configuration.static_mounts {
+            if let Err(e) = CopyFilter::new(&mount.include, &mount.exclude) {
+                self.report(&config_path, format!("static: {}", e));
+            }
+            if !mount.from.is_dir() {
+                self.report(&mount.from, "static directory does not exist");
+            }
+        }
+    }
+
+    fn check_vite_manifest(&mut self) -> Option<ViteManifest> {
+        let vite = self
+            .configuration
+            .bundler
+            .as_ref()
+            .and_then(|bundler| bundler.vite.as_ref())
+            .filter(|vite| vite.enabled)?;
+
+        let manifest_path = PathBuf::from(&vite.manifest_path);
+        match parse_manifest(manifest_path.clone(), &self.configuration.root_directory) {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                let path = self.configuration.root_directory.join(manifest_path);
+                self.report(&path, e);
+                None
+            }
+        }
+    }
+
+    fn compile(&mut self, path: &Path) {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => return self.report(path, e.to_string()),
+        };
+
+        match Template::compile(&source) {
+            Ok(template) => self.templates.push((path.to_path_buf(), template)),
+            Err(e) => self.report(path, format!("invalid template: {}", e)),
+        }
+    }
+
+    /// Reports partials that are not registered and vite entries that are
+    /// not in the manifest
+    fn check_references(&mut self, manifest: Option<&ViteManifest>) {
+        let templates = std::mem::take(&mut self.templates);
+
+        for (path, template) in &templates {
+            let mut references = References::default();
+            references.collect(template);
+
+            for partial in &references.partials {
+                if !self.partials.contains(partial) && !references.inline_partials.contains(partial)
+                {
+                    self.report(path, format!("unknown partial {}", partial));
+                }
+            }
+
+            if let Some(manifest) = manifest {
+                for entry in &references.vite_entries {
+                    if get_file(manifest, entry).is_err() {
+                        self.report(path, format!("vite entry {} is not in the manifest", entry));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Names referenced by a template
+#[derive(Default)]
+struct References {
+    partials: Vec<String>,
+    inline_partials: HashSet<String>,
+    vite_entries: Vec<String>,
+}
+
+impl References {
+    fn collect(&mut self, template: &Template) {
+        for element in &template.elements {
+            match element {
+                TemplateElement::Expression(helper)
+                | TemplateElement::HtmlExpression(helper)
+                | TemplateElement::HelperBlock(helper) => self.collect_helper(helper),
+                TemplateElement::PartialExpression(partial)
+                | TemplateElement::PartialBlock(partial) => {
+                    if let Some(name) = partial.name.as_name()
+                        && !name.starts_with('@')
+                    {
+                        self.partials.push(name.to_string());
+                    }
+                    if let Some(inner) = &partial.template {
+                        self.collect(inner);
+                    }
+                }
+                TemplateElement::DecoratorBlock(decorator) => {
+                    if decorator.name.as_name() == Some("inline")
+                        && let Some(Parameter::Literal(serde_json::Value::String(name))) =
+                            decorator.params.first()
+                    {
+                        self.inline_partials.insert(name.clone());
+                    }
+                    if let Some(inner) = &decorator.template {
+                        self.collect(inner);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn collect_helper(&mut self, helper: &HelperTemplate) {
+        if helper.name.as_name() == Some("vite_url")
+            && let Some(Parameter::Literal(serde_json::Value::String(entry))) =
+                helper.params.first()
+        {
+            self.vite_entries.push(entry.clone());
+        }
+
+        if let Some(inner) = &helper.template {
+            self.collect(inner);
+        }
+        if let Some(inner) = &helper.inverse {
+            self.collect(inner);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StaticMount;
+    use tempfile::TempDir;
+
+    fn messages(problems: &[Problem]) -> Vec<String> {
+        problems.iter().map(|p| p.message.clone()).collect()
+    }
+
+    #[test]
+    fn test_valid_project_has_no_problems() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("pages/posts")).unwrap();
+        fs::create_dir_all(root.join("partials")).unwrap();
+        fs::create_dir_all(root.join("content/posts")).unwrap();
+        fs::write(root.join("partials/nav.hbs"), "<nav></nav>").unwrap();
+        fs::write(
+            root.join("pages/index.hbs"),
+            "{{#*inline \"extra\"}}x{{/inline}}{{> nav}}{{> extra}}",
+        )
+        .unwrap();
+        fs::write(root.join("pages/posts/details.hbs"), "{{fm.title}}").unwrap();
+        fs::write(root.join("content/posts/a.md"), "---\ntitle: A\n---\nBody").unwrap();
+
+        let problems = check_config(&Config::default(), root);
+        assert!(problems.is_empty(), "{:?}", problems);
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("pages")).unwrap();
+        fs::create_dir_all(root.join("content/posts")).unwrap();
+        fs::write(root.join("content/stray.md"), "stray").unwrap();
+        fs::write(
+            root.join("content/posts/bad.md"),
+            "---\ntitle: [\n---\nBody",
+        )
+        .unwrap();
+        fs::write(root.join("pages/index.hbs"), "{{> missing}}").unwrap();
+        fs::write(root.join("pages/broken.hbs"), "{{#if x}}").unwrap();
+        fs::write(
+            root.join("content/posts/late.md"),
+            "---\ntitle: Late\npublish_date: tomorrow\n---\nBody",
+        )
+        .unwrap();
+
+        let config = Config {
+            assets_ignore: vec!["[".to_string()],
+            static_mounts: vec![StaticMount {
+                from: "./public".to_string(),
+                to: "/".to_string(),
+                include: Vec::new(),
+                exclude: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        let problems = check_config(&config, root);
+        let messages = messages(&problems);
+
+        assert_eq!(problems.len(), 8, "{:?}", messages);
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.starts_with("invalid publish_date"))
+        );
+        assert!(messages.iter().any(|m| m.starts_with("invalid template")));
+        assert!(messages.iter().any(|m| m == "unknown partial missing"));
+        assert!(messages.iter().any(|m| m.contains("top level")));
+        assert!(messages.iter().any(|m| m.contains("no details page")));
+        assert!(messages.iter().any(|m| m.contains("frontmatter YAML")));
+        assert!(messages.iter().any(|m| m.starts_with("assets_ignore")));
+        assert!(
+            messages
+                .iter()
+                .any(|m| m == "static directory does not exist")
+        );
+    }
+
+    #[test]
+    fn test_reports_conflicts_next_to_invalid_items() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("pages/blog")).unwrap();
+        fs::create_dir_all(root.join("content/blog")).unwrap();
+        fs::write(root.join("pages/about.hbs"), "about").unwrap();
+        fs::write(root.join("pages/about.html"), "about").unwrap();
+        fs::write(root.join("pages/blog/details.hbs"), "{{title}}").unwrap();
+        fs::write(root.join("content/blog/bad.md"), "---\ntitle: [\n---\nBody").unwrap();
+        fs::write(
+            root.join("content/blog/about.md"),
+            "---\ntitle: About\n---\nBody",
+        )
+        .unwrap();
+
+        let problems = check_config(&Config::default(), root);
+        let messages = messages(&problems);
+
+        assert!(messages.iter().any(|m| m.contains("frontmatter YAML")));
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.starts_with("URL /about is produced by"))
+        );
+    }
+
+    #[test]
+    fn test_reports_missing_vite_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("pages")).unwrap();
+        fs::write(
+            root.join("manifest.json"),
+            r#"{"main.js": {"file": "assets/main-123.js"}}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("pages/index.hbs"),
+            "{{vite_url \"main.js\"}}{{vite_url \"other.js\"}}",
+        )
+        .unwrap();
+
+        let config: Config =
+            toml::from_str("[bundler.vite]\nenabled = true\nmanifest_path = \"manifest.json\"")
+                .unwrap();
+        let problems = check_config(&config, root);
+
+        assert_eq!(
+            messages(&problems),
+            vec!["vite entry other.js is not in the manifest".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_reports_missing_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let problems = check(temp_dir.path(), None);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].path, temp_dir.path().join("balzac.toml"));
+    }
+
+    #[test]
+    fn test_reports_missing_overlay() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("balzac.toml"), "").unwrap();
+
+        let problems = check(temp_dir.path(), Some("staging"));
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            problems[0].path,
+            temp_dir.path().join("balzac.staging.toml")
+        );
+    }
+}
@@ -1,16 +1,34 @@
 use std::fs;
 use std::path::Path;
 
-use crate::config::{Config, CreateConfigError, InitFeature};
-use crate::hooks::{HookExecutor, HookPhase};
-use crate::renderer::{HandlebarsRenderer, Renderer};
-use crate::sitemap::SitePages;
-use crate::{
-    add_assets, discover_collections, discover_static_pages, make_dist_folder,
-    render_collection_items, render_pages, write_sitemap,
-};
-
-pub fn init(path: &Path, features: &[InitFeature]) {
+use chrono::{DateTime, Utc};
+
+use crate::archetype;
+use crate::build::{BuildError, BuildReport, Builder};
+use crate::check;
+use crate::config::{Config, CreateConfigError, InitFeature, ResolvedConfig};
+use crate::report;
+use crate::routes::Routes;
+use crate::serve::{self, LiveReload};
+use crate::starter::{self, StarterTemplate};
+use crate::watch::{self, WatchTargets};
+
+/// Options shared by the commands that build the project
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuildOptions<'a> {
+    /// Environment whose overlay is merged over balzac.toml
+    pub environment: Option<&'a str>,
+    /// Ignore the build cache
+    pub clean: bool,
+    /// Clean the output directory even if it is not safe to delete
+    pub force: bool,
+    /// Build drafts, even if the configuration does not
+    pub drafts: bool,
+    /// Time the publication window of collection items is checked against
+    pub now: Option<DateTime<Utc>>,
+}
+
+pub fn init(path: &Path, features: &[InitFeature], template: Option<StarterTemplate>) {
     log::info!("Initializing new balzac project at {:?}", path);
 
     if !path.exists()
@@ -26,7 +44,8 @@ pub fn init(path: &Path, features: &[InitFeature]) {
         Some(features)
     };
 
-    match Config::create(path, features) {
+    let global = template.map(|template| template.global());
+    match Config::create_with_global(path, features, global) {
         Ok(()) => log::info!("Created balzac.toml"),
         Err(CreateConfigError::AlreadyExists) => {
             eprintln!(
@@ -45,7 +64,14 @@ pub fn init(path: &Path, features: &[InitFeature]) {
         }
     }
 
-    let directories = ["pages", "layouts", "partials", "assets", "content"];
+    let directories = [
+        "pages",
+        "layouts",
+        "partials",
+        "assets",
+        "content",
+        "archetypes",
+    ];
     for dir in &directories {
         let dir_path = path.join(dir);
         if !dir_path.exists() {
@@ -57,109 +83,253 @@ pub fn init(path: &Path, features: &[InitFeature]) {
         }
     }
 
+    match starter::write_files(path, template, features.unwrap_or(&[])) {
+        Ok(created) => {
+            for file in created {
+                log::info!("Created {}", file.display());
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: Could not create starter files: {}", e);
+            std::process::exit(1);
+        }
+    }
+
     log::info!("Project initialized successfully!");
+    if features.is_some_and(|features| features.contains(&InitFeature::Vite)) {
+        log::info!(
+            "Run `npm install` before the first build, the render_init_before hook runs vite"
+        );
+    }
 }
 
-pub fn build(path: &Path) {
-    let start = std::time::Instant::now();
-    let config_path = path.join("balzac.toml");
-    let config_content = fs::read_to_string(&config_path).expect("Config file not found");
-    let parsed_config: Config = toml::from_str(&config_content).expect("Could not parse config");
-    log::info!("Parsed configuration file (took {:?})", start.elapsed());
-
-    let resolved_config = parsed_config.resolve(path);
-
-    let hook_executor = HookExecutor::new(parsed_config.hooks.as_ref(), path);
-    hook_executor.execute(HookPhase::RenderInitBefore);
-
-    let start = std::time::Instant::now();
-    let mut render: HandlebarsRenderer<'_> = HandlebarsRenderer::new(&resolved_config);
-    render.init(&resolved_config);
-    log::info!("Renderer is initialized (took {:?})", start.elapsed());
+pub fn build(path: &Path, options: &BuildOptions, report_path: Option<&Path>) {
+    match run_build(path, options) {
+        Ok(report) => {
+            log_report(&report);
+            if let Some(report_path) = report_path {
+                write_report(&report, report_path);
+            }
+        }
+        Err(e) => {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
 
-    hook_executor.execute(HookPhase::RenderInitAfter);
-    hook_executor.execute(HookPhase::BuildBefore);
+pub fn new(path: &Path, collection: &str, title: &str) {
+    let resolved_config = match Config::load(path) {
+        Ok(config) => config.resolve(path),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    let start = std::time::Instant::now();
-    match make_dist_folder(&resolved_config) {
-        Ok(()) => {}
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    match archetype::create_content(&resolved_config, collection, title, &date) {
+        Ok(content_path) => log::info!("Created {}", content_path.display()),
         Err(e) => {
-            log::error!("Error creating output directory: {}", e);
+            eprintln!("Error: {}", e);
             std::process::exit(1);
         }
     }
-    log::info!("Created output directory (took {:?})", start.elapsed());
+}
 
+pub fn check(path: &Path, environment: Option<&str>) {
     let start = std::time::Instant::now();
-    let static_pages = match discover_static_pages(&resolved_config) {
-        Ok(pages) => pages,
+    let problems = check::check(path, environment);
+
+    for problem in &problems {
+        log::error!("{}", problem);
+    }
+
+    if problems.is_empty() {
+        log::info!("No problems found (took {:?})", start.elapsed());
+    } else {
+        log::error!(
+            "Found {} problem(s) (took {:?})",
+            problems.len(),
+            start.elapsed()
+        );
+        std::process::exit(1);
+    }
+}
+
+/// Prints every page of the site, then fails if pages share a URL or an
+/// output file
+pub fn routes(path: &Path, options: &BuildOptions, json: bool) {
+    let mut config = match Config::load_with_environment(path, options.environment) {
+        Ok(config) => config,
         Err(e) => {
-            log::error!("Error discovering static pages: {}", e);
+            eprintln!("Error: {}", e);
             std::process::exit(1);
         }
     };
-    let collection_pages = match discover_collections(&resolved_config) {
-        Ok(pages) => pages,
+    if options.drafts {
+        config.drafts = true;
+    }
+    let resolved_config = config.resolve(path);
+
+    let routes = match Routes::discover(&resolved_config) {
+        Ok(routes) => routes,
         Err(e) => {
-            log::error!("Error discovering collections: {}", e);
+            eprintln!("Error: Could not discover pages: {}", e);
             std::process::exit(1);
         }
     };
 
-    let mut site_pages = SitePages::new();
-    site_pages.add_pages(static_pages);
-    site_pages.add_pages(collection_pages);
-    log::info!(
-        "Discovered {} pages (took {:?})",
-        site_pages.all().len(),
-        start.elapsed()
-    );
+    if json {
+        match serde_json::to_string_pretty(&routes.to_json()) {
+            Ok(content) => println!("{}", content),
+            Err(e) => {
+                eprintln!("Error: Could not serialize routes: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print!("{}", routes.to_table(&resolved_config));
+    }
 
-    hook_executor.execute(HookPhase::RenderBefore);
+    for conflict in &routes.conflicts {
+        log::error!("{}", conflict);
+    }
+    if !routes.conflicts.is_empty() {
+        log::error!("Found {} conflict(s)", routes.conflicts.len());
+        std::process::exit(1);
+    }
+}
 
-    let start = std::time::Instant::now();
-    match render_pages(&resolved_config, site_pages.all(), &render) {
-        Ok(()) => {}
+pub fn watch(path: &Path, options: &BuildOptions) {
+    let resolved_config = match Config::load_with_environment(path, options.environment) {
+        Ok(config) => config.resolve(path),
         Err(e) => {
-            log::error!("Error rendering static pages: {}", e);
+            log::error!("{}", e);
             std::process::exit(1);
         }
+    };
+
+    match run_build(path, options) {
+        Ok(report) => log_report(&report),
+        Err(e) => log::error!("Build failed: {}", e),
     }
-    log::info!("Rendered static pages (took {:?})", start.elapsed());
 
-    let start = std::time::Instant::now();
-    match render_collection_items(&resolved_config, site_pages.all(), &render) {
-        Ok(()) => {}
+    log::info!("Watching for changes");
+    rebuild_on_change(path, options, &resolved_config, || {});
+}
+
+pub fn serve(path: &Path, options: &BuildOptions, address: &str) {
+    let resolved_config = match Config::load_with_environment(path, options.environment) {
+        Ok(config) => config.resolve(path),
         Err(e) => {
-            log::error!("Error rendering collections: {}", e);
+            log::error!("{}", e);
             std::process::exit(1);
         }
+    };
+
+    match run_build(path, options) {
+        Ok(report) => log_report(&report),
+        Err(e) => log::error!("Build failed: {}", e),
     }
-    log::info!("Rendered collections (took {:?})", start.elapsed());
 
-    hook_executor.execute(HookPhase::RenderAfter);
+    let live_reload = LiveReload::new();
+    if let Err(e) = serve::start(
+        resolved_config.output_directory.clone(),
+        address,
+        live_reload.clone(),
+    ) {
+        log::error!("{}", e);
+        std::process::exit(1);
+    }
+    log::info!("Serving site at http://{}", address);
 
-    let start = std::time::Instant::now();
-    match write_sitemap(&resolved_config, &site_pages) {
-        Ok(()) => {}
-        Err(e) => {
-            log::error!("Error writing sitemap: {}", e);
-            std::process::exit(1);
+    rebuild_on_change(path, options, &resolved_config, || live_reload.reload());
+}
+
+/// Rebuilds the project every time one of its sources changes, calling
+/// `on_success` after each successful build
+///
+/// Build and watch errors are logged and watching continues, so only a
+/// watcher that cannot be started terminates the process. The watched
+/// directories follow changes to the configuration.
+fn rebuild_on_change<F>(
+    path: &Path,
+    options: &BuildOptions,
+    resolved_config: &ResolvedConfig,
+    on_success: F,
+) where
+    F: Fn(),
+{
+    let targets = WatchTargets::from_config(resolved_config);
+    // Only the first build can be a clean one
+    let options = BuildOptions {
+        clean: false,
+        ..*options
+    };
+    let result = watch::watch(targets, |changed| {
+        log::info!("Detected changes in {} file(s), rebuilding", changed.len());
+        match run_build(path, &options) {
+            Ok(report) => {
+                log_report(&report);
+                on_success();
+            }
+            Err(e) => log::error!("Build failed: {}", e),
         }
+
+        // Directories may have been added to or moved in balzac.toml. An
+        // invalid configuration was just reported by the build, in which
+        // case the current targets are kept.
+        Config::load_with_environment(path, options.environment)
+            .ok()
+            .map(|config| WatchTargets::from_config(&config.resolve(path)))
+    });
+
+    if let Err(e) = result {
+        log::error!("Error watching files: {}", e);
+        std::process::exit(1);
     }
-    if resolved_config.base_url.is_some() {
-        log::info!("Generated sitemap (took {:?})", start.elapsed());
-    }
+}
 
+fn run_build(path: &Path, options: &BuildOptions) -> Result<BuildReport, BuildError> {
     let start = std::time::Instant::now();
-    match add_assets(&resolved_config) {
-        Ok(()) => {}
+    let mut builder = Builder::from_root_with_environment(path, options.environment)?;
+    log::info!("Parsed configuration file (took {:?})", start.elapsed());
+
+    if options.drafts {
+        builder = builder.drafts(true);
+    }
+    if let Some(now) = options.now {
+        builder = builder.now(now);
+    }
+    builder.clean(options.clean).force(options.force).build()
+}
+
+fn write_report(report: &BuildReport, report_path: &Path) {
+    let content = match serde_json::to_string_pretty(&report::to_json(report)) {
+        Ok(content) => content,
         Err(e) => {
-            log::error!("Error handling assets: {}", e);
+            log::error!("Could not serialize build report: {}", e);
             std::process::exit(1);
         }
+    };
+
+    if let Err(e) = fs::write(report_path, content) {
+        log::error!(
+            "Could not write build report to {}: {}",
+            report_path.display(),
+            e
+        );
+        std::process::exit(1);
     }
-    log::info!("Handled assets (took {:?})", start.elapsed());
+    log::info!("Wrote build report to {}", report_path.display());
+}
 
-    hook_executor.execute(HookPhase::BuildAfter);
+fn log_report(report: &BuildReport) {
+    log::info!(
+        "Build finished, rendered {} of {} pages (took {:?})",
+        report.rendered_pages,
+        report.site_pages.all().len(),
+        report.duration
+    );
 }
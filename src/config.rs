@@ -1,10 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::io;
-use std::path::Path;
+use std::path::{Component, Path};
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::interpolate::{InterpolationError, interpolate_env};
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SitemapConfig {
     #[serde(default = "default_sitemap_enabled")]
@@ -43,7 +46,7 @@ fn is_default_sitemap_filename(s: &String) -> bool {
     s == &default_sitemap_filename()
 }
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Default)]
 pub struct Hooks {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub build_before: Option<String>,
@@ -73,6 +76,168 @@ pub struct Bundler {
     pub vite: Option<ViteBundler>,
 }
 
+/// A directory copied into the output directory, configured with a
+/// `[[static]]` entry
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StaticMount {
+    /// Directory to copy, relative to the project root
+    pub from: String,
+    /// Path of the site the directory is copied to
+    #[serde(default = "default_static_to")]
+    pub to: String,
+    /// Patterns of the files to copy, every file is copied when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Patterns of the files that are not copied
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+/// Settings of a single collection, configured with a
+/// `[collections.<name>]` table
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CollectionConfig {
+    /// Details template, relative to the project root, defaults to
+    /// `<pages_directory>/<name>/details.hbs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    /// Directory of the output directory the items are written to, defaults
+    /// to the name of the collection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// URL pattern of the items, like `/blog/{year}/{month}/{slug}/`, see
+    /// [`crate::permalink`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permalink: Option<String>,
+    /// Give templates the section tree of the whole collection, which renders
+    /// every item again whenever one of them changes
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub tree: bool,
+    /// List template, relative to the project root, defaults to
+    /// `<pages_directory>/<name>/list.hbs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
+    /// Number of items per list page, defaults to 10
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paginate: Option<usize>,
+}
+
+/// Number of items per list page of collections without a `paginate` setting
+pub const DEFAULT_PAGINATE: usize = 10;
+
+/// A [`CollectionConfig`] with its paths resolved
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedCollectionConfig {
+    pub details: Option<std::path::PathBuf>,
+    pub output: Option<String>,
+    pub permalink: Option<String>,
+    pub tree: bool,
+    pub list: Option<std::path::PathBuf>,
+    pub paginate: Option<usize>,
+}
+
+impl ResolvedCollectionConfig {
+    /// Number of items per list page
+    pub fn page_size(&self) -> usize {
+        self.paginate.unwrap_or(DEFAULT_PAGINATE).max(1)
+    }
+}
+
+/// Settings of a taxonomy, like tags or categories, configured with a
+/// `[taxonomies.<name>]` table
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TaxonomyConfig {
+    /// Frontmatter field the terms of an item are read from, defaults to the
+    /// name of the taxonomy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Collections whose items are classified, defaults to all of them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collections: Vec<String>,
+    /// Directory of the output directory the taxonomy pages are written to,
+    /// defaults to the name of the taxonomy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Template of the term pages, relative to the project root, defaults to
+    /// `<pages_directory>/<name>/term.hbs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term: Option<String>,
+    /// Template of the index page, relative to the project root, defaults to
+    /// `<pages_directory>/<name>/list.hbs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
+}
+
+/// A [`TaxonomyConfig`] with its defaults applied and its paths resolved
+#[derive(Debug, Clone)]
+pub struct ResolvedTaxonomyConfig {
+    pub field: String,
+    pub collections: Vec<String>,
+    pub output: String,
+    pub term: std::path::PathBuf,
+    pub list: std::path::PathBuf,
+}
+
+impl ResolvedTaxonomyConfig {
+    /// Whether the items of the collection `name` are classified
+    pub fn classifies(&self, name: &str) -> bool {
+        self.collections.is_empty() || self.collections.iter().any(|c| c == name)
+    }
+}
+
+/// A [`StaticMount`] with its paths resolved
+#[derive(Debug, Clone)]
+pub struct ResolvedStaticMount {
+    pub from: std::path::PathBuf,
+    pub to: std::path::PathBuf,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+/// How page URLs map to files of the output directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlStyle {
+    /// `/about` is written to `about.html`, which needs a server that
+    /// resolves extensionless URLs
+    #[default]
+    Extensionless,
+    /// `/about/` is written to `about/index.html`
+    Pretty,
+    /// `/about.html` is written to `about.html`
+    Html,
+}
+
+impl UrlStyle {
+    /// Returns the URL of the page at `route`, along with its output path
+    /// relative to the output directory
+    ///
+    /// `route` is the `/` separated path of the page without extension, like
+    /// `docs/install`. Index pages are served at the URL of their directory
+    /// whatever the style.
+    pub fn locate(&self, route: &str) -> (String, std::path::PathBuf) {
+        let directory = if route == "index" {
+            Some("")
+        } else {
+            route.strip_suffix("/index")
+        };
+        if let Some(directory) = directory {
+            let url = if directory.is_empty() {
+                "/".to_string()
+            } else {
+                format!("/{}/", directory)
+            };
+            return (url, Path::new(directory).join("index.html"));
+        }
+
+        match self {
+            UrlStyle::Extensionless => (format!("/{}", route), format!("{}.html", route).into()),
+            UrlStyle::Pretty => (format!("/{}/", route), Path::new(route).join("index.html")),
+            UrlStyle::Html => (format!("/{}.html", route), format!("{}.html", route).into()),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Config {
     #[serde(
@@ -100,11 +265,39 @@ pub struct Config {
         skip_serializing_if = "is_default_assets_directory"
     )]
     pub assets_directory: String,
+    /// Path of the output directory the assets are copied to, `/` copies them
+    /// to the root of the site
+    #[serde(
+        default = "default_assets_mount",
+        skip_serializing_if = "is_default_assets_mount"
+    )]
+    pub assets_mount: String,
+    /// Patterns of the asset files that are not copied, patterns without a
+    /// `/` are matched against file names
+    #[serde(
+        default = "default_assets_ignore",
+        skip_serializing_if = "is_default_assets_ignore"
+    )]
+    pub assets_ignore: Vec<String>,
+    /// Directories copied into the output directory besides the assets
+    #[serde(default, rename = "static", skip_serializing_if = "Vec::is_empty")]
+    pub static_mounts: Vec<StaticMount>,
+    /// Settings of the collections, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub collections: BTreeMap<String, CollectionConfig>,
+    /// Taxonomies the collection items are classified in, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub taxonomies: BTreeMap<String, TaxonomyConfig>,
     #[serde(
         default = "default_content_directory",
         skip_serializing_if = "is_default_content_directory"
     )]
     pub content_directory: String,
+    #[serde(
+        default = "default_archetypes_directory",
+        skip_serializing_if = "is_default_archetypes_directory"
+    )]
+    pub archetypes_directory: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub global: Option<HashMap<String, serde_json::Value>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -115,6 +308,19 @@ pub struct Config {
     pub base_url: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub sitemap: Option<SitemapConfig>,
+    /// Build collection items marked as drafts
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub drafts: bool,
+    /// How page URLs map to output files
+    #[serde(default, skip_serializing_if = "is_default_url_style")]
+    pub url_style: UrlStyle,
+    /// Environment whose overlay file was merged into this configuration
+    #[serde(skip)]
+    pub environment: Option<String>,
+    /// Time the publication window of collection items is checked against,
+    /// defaults to the time of the build
+    #[serde(skip)]
+    pub now: Option<DateTime<Utc>>,
 }
 
 impl Default for Config {
@@ -125,12 +331,22 @@ impl Default for Config {
             layouts_directory: default_layouts_directory(),
             partials_directory: default_partials_directory(),
             assets_directory: default_assets_directory(),
+            assets_mount: default_assets_mount(),
+            assets_ignore: default_assets_ignore(),
+            static_mounts: Vec::new(),
+            collections: BTreeMap::new(),
+            taxonomies: BTreeMap::new(),
             content_directory: default_content_directory(),
+            archetypes_directory: default_archetypes_directory(),
             global: None,
             hooks: None,
             bundler: None,
             base_url: None,
             sitemap: None,
+            drafts: false,
+            url_style: UrlStyle::default(),
+            environment: None,
+            now: None,
         }
     }
 }
@@ -144,12 +360,75 @@ impl Config {
             layouts_directory: self.resolve_path(&self.layouts_directory, root),
             partials_directory: self.resolve_path(&self.partials_directory, root),
             assets_directory: self.resolve_path(&self.assets_directory, root),
+            assets_output_directory: self
+                .resolve_path(&self.output_directory, root)
+                .join(self.assets_mount.trim_start_matches('/')),
+            assets_ignore: self.assets_ignore.clone(),
+            static_mounts: self
+                .static_mounts
+                .iter()
+                .map(|mount| ResolvedStaticMount {
+                    from: self.resolve_path(&mount.from, root),
+                    to: self
+                        .resolve_path(&self.output_directory, root)
+                        .join(mount.to.trim_start_matches('/')),
+                    include: mount.include.clone(),
+                    exclude: mount.exclude.clone(),
+                })
+                .collect(),
+            collections: self
+                .collections
+                .iter()
+                .map(|(name, collection)| {
+                    let resolved = ResolvedCollectionConfig {
+                        details: collection
+                            .details
+                            .as_ref()
+                            .map(|details| self.resolve_path(details, root)),
+                        output: collection.output.clone(),
+                        permalink: collection.permalink.clone(),
+                        tree: collection.tree,
+                        list: collection
+                            .list
+                            .as_ref()
+                            .map(|list| self.resolve_path(list, root)),
+                        paginate: collection.paginate,
+                    };
+                    (name.clone(), resolved)
+                })
+                .collect(),
+            taxonomies: self
+                .taxonomies
+                .iter()
+                .map(|(name, taxonomy)| {
+                    let template = |path: &Option<String>, default: &str| match path {
+                        Some(path) => self.resolve_path(path, root),
+                        None => self
+                            .resolve_path(&self.pages_directory, root)
+                            .join(name)
+                            .join(default),
+                    };
+                    let resolved = ResolvedTaxonomyConfig {
+                        field: taxonomy.field.clone().unwrap_or_else(|| name.clone()),
+                        collections: taxonomy.collections.clone(),
+                        output: taxonomy.output.clone().unwrap_or_else(|| name.clone()),
+                        term: template(&taxonomy.term, "term.hbs"),
+                        list: template(&taxonomy.list, "list.hbs"),
+                    };
+                    (name.clone(), resolved)
+                })
+                .collect(),
             content_directory: self.resolve_path(&self.content_directory, root),
+            archetypes_directory: self.resolve_path(&self.archetypes_directory, root),
             global: self.global.clone(),
             hooks: self.hooks.clone(),
             bundler: self.bundler.clone(),
             base_url: self.base_url.clone(),
             sitemap: self.sitemap.clone(),
+            drafts: self.drafts,
+            url_style: self.url_style,
+            environment: self.environment.clone(),
+            now: self.now,
         }
     }
 
@@ -166,12 +445,47 @@ pub struct ResolvedConfig {
     pub layouts_directory: std::path::PathBuf,
     pub partials_directory: std::path::PathBuf,
     pub assets_directory: std::path::PathBuf,
+    pub assets_output_directory: std::path::PathBuf,
+    pub assets_ignore: Vec<String>,
+    pub static_mounts: Vec<ResolvedStaticMount>,
+    pub collections: BTreeMap<String, ResolvedCollectionConfig>,
+    pub taxonomies: BTreeMap<String, ResolvedTaxonomyConfig>,
     pub content_directory: std::path::PathBuf,
+    pub archetypes_directory: std::path::PathBuf,
     pub global: Option<std::collections::HashMap<String, serde_json::Value>>,
     pub hooks: Option<Hooks>,
     pub bundler: Option<Bundler>,
     pub base_url: Option<String>,
     pub sitemap: Option<SitemapConfig>,
+    pub drafts: bool,
+    pub url_style: UrlStyle,
+    pub environment: Option<String>,
+    pub now: Option<DateTime<Utc>>,
+}
+
+impl ResolvedConfig {
+    /// Settings of the collection `name`, defaults if it has no
+    /// `[collections.<name>]` table
+    pub fn collection(&self, name: &str) -> ResolvedCollectionConfig {
+        self.collections.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Template the items of the collection `name` are rendered with
+    pub fn details_page_path(&self, name: &str) -> std::path::PathBuf {
+        self.collections
+            .get(name)
+            .and_then(|collection| collection.details.clone())
+            .unwrap_or_else(|| self.pages_directory.join(name).join("details.hbs"))
+    }
+
+    /// Template the list pages of the collection `name` are rendered with,
+    /// the collection has no list pages if it does not exist
+    pub fn list_page_path(&self, name: &str) -> std::path::PathBuf {
+        self.collections
+            .get(name)
+            .and_then(|collection| collection.list.clone())
+            .unwrap_or_else(|| self.pages_directory.join(name).join("list.hbs"))
+    }
 }
 
 fn default_vite_manifest_path() -> String {
@@ -198,13 +512,76 @@ fn is_default_assets_directory(s: &String) -> bool {
     s == &default_assets_directory()
 }
 
+fn is_default_assets_mount(s: &String) -> bool {
+    s == &default_assets_mount()
+}
+
+fn is_default_assets_ignore(patterns: &Vec<String>) -> bool {
+    patterns == &default_assets_ignore()
+}
+
 fn is_default_content_directory(s: &String) -> bool {
     s == &default_content_directory()
 }
 
+fn is_default_archetypes_directory(s: &String) -> bool {
+    s == &default_archetypes_directory()
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+fn is_default_url_style(style: &UrlStyle) -> bool {
+    *style == UrlStyle::default()
+}
+
+/// Name of the overlay file of `environment`
+pub fn overlay_filename(environment: &str) -> String {
+    format!("balzac.{}.toml", environment)
+}
+
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, overlay_value) in overlay {
+        match (base.get_mut(&key), overlay_value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table);
+            }
+            (_, overlay_value) => {
+                base.insert(key, overlay_value);
+            }
+        }
+    }
+}
+
+fn interpolate_table(table: &mut toml::Table, prefix: &str) -> Result<(), ConfigError> {
+    for (key, value) in table.iter_mut() {
+        interpolate_value(value, &format!("{}{}", prefix, key))?;
+    }
+    Ok(())
+}
+
+fn interpolate_value(value: &mut toml::Value, key: &str) -> Result<(), ConfigError> {
+    match value {
+        toml::Value::String(s) => {
+            *s = interpolate_env(s).map_err(|e| ConfigError::Interpolation(key.to_string(), e))?;
+        }
+        toml::Value::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                interpolate_value(value, &format!("{}[{}]", key, index))?;
+            }
+        }
+        toml::Value::Table(table) => interpolate_table(table, &format!("{}.", key))?,
+        _ => {}
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InitFeature {
     Sitemap,
+    Vite,
+    Hooks,
 }
 
 #[derive(Debug)]
@@ -246,8 +623,180 @@ impl From<toml::ser::Error> for CreateConfigError {
     }
 }
 
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    /// The overlay file of an environment could not be read, the first field
+    /// being its file name
+    OverlayIo(String, io::Error),
+    OverlayParse(String, toml::de::Error),
+    /// A `${VAR}` reference could not be expanded, the first field being the
+    /// key of the value it appears in
+    Interpolation(String, InterpolationError),
+    /// A mount point or another output setting would write files outside of
+    /// the output directory, the fields being its key and its value
+    InvalidOutputPath(String, String),
+}
+
+impl ConfigError {
+    /// Name of the configuration file the error comes from
+    pub fn filename(&self) -> &str {
+        match self {
+            ConfigError::Io(_)
+            | ConfigError::Parse(_)
+            | ConfigError::Interpolation(..)
+            | ConfigError::InvalidOutputPath(..) => "balzac.toml",
+            ConfigError::OverlayIo(filename, _) | ConfigError::OverlayParse(filename, _) => {
+                filename
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "Could not read balzac.toml: {}", e),
+            ConfigError::Parse(e) => write!(f, "Could not parse balzac.toml: {}", e),
+            ConfigError::OverlayIo(filename, e) => {
+                write!(f, "Could not read {}: {}", filename, e)
+            }
+            ConfigError::OverlayParse(filename, e) => {
+                write!(f, "Could not parse {}: {}", filename, e)
+            }
+            ConfigError::Interpolation(key, e) => write!(f, "Could not expand {}: {}", key, e),
+            ConfigError::InvalidOutputPath(key, value) => write!(
+                f,
+                "Invalid {} {:?}: expected a path inside the output directory, without ..",
+                key, value
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(e) | ConfigError::OverlayIo(_, e) => Some(e),
+            ConfigError::Parse(e) | ConfigError::OverlayParse(_, e) => Some(e),
+            ConfigError::Interpolation(_, e) => Some(e),
+            ConfigError::InvalidOutputPath(..) => None,
+        }
+    }
+}
+
+/// Whether `path`, written like a URL where a leading / stands for the root
+/// of the output directory, stays inside the output directory
+fn is_inside_output(path: &str) -> bool {
+    Path::new(path.trim_start_matches('/'))
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(err: toml::de::Error) -> Self {
+        ConfigError::Parse(err)
+    }
+}
+
 impl Config {
+    /// Reads and parses the balzac.toml file found in `root`
+    pub fn load(root: &Path) -> Result<Config, ConfigError> {
+        Self::load_with_environment(root, None)
+    }
+
+    /// Reads and parses the balzac.toml file found in `root`, deep merging the
+    /// `balzac.<environment>.toml` overlay over it when an environment is given
+    ///
+    /// Tables are merged key by key, any other value of the overlay (arrays
+    /// included) replaces the one of the base file. `${VAR}` references in
+    /// string values are then expanded from the process environment.
+    pub fn load_with_environment(
+        root: &Path,
+        environment: Option<&str>,
+    ) -> Result<Config, ConfigError> {
+        let config_content = fs::read_to_string(root.join("balzac.toml"))?;
+        let mut table: toml::Table = toml::from_str(&config_content)?;
+
+        if let Some(environment) = environment {
+            let filename = overlay_filename(environment);
+            let overlay_content = fs::read_to_string(root.join(&filename))
+                .map_err(|e| ConfigError::OverlayIo(filename.clone(), e))?;
+            let overlay: toml::Table = toml::from_str(&overlay_content)
+                .map_err(|e| ConfigError::OverlayParse(filename.clone(), e))?;
+            merge_tables(&mut table, overlay);
+            log::debug!("Merged {} over balzac.toml", filename);
+        }
+
+        interpolate_table(&mut table, "")?;
+
+        let mut config: Config = table.try_into()?;
+        config.environment = environment.map(str::to_string);
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the settings that cannot be rejected while parsing, like mount
+    /// points or collection and taxonomy outputs leaving the output directory
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mounts = std::iter::once(("assets_mount".to_string(), &self.assets_mount)).chain(
+            self.static_mounts
+                .iter()
+                .map(|mount| ("static.to".to_string(), &mount.to)),
+        );
+        let collection_outputs = self.collections.iter().filter_map(|(name, collection)| {
+            let output = collection.output.as_ref()?;
+            Some((format!("collections.{}.output", name), output))
+        });
+        // Taxonomy pages are written to the name of the taxonomy by default,
+        // which is as much user input as the output setting
+        let taxonomy_outputs = self.taxonomies.iter().map(|(name, taxonomy)| {
+            (
+                format!("taxonomies.{}.output", name),
+                taxonomy.output.as_ref().unwrap_or(name),
+            )
+        });
+        for (key, path) in mounts.chain(collection_outputs).chain(taxonomy_outputs) {
+            if !is_inside_output(path) {
+                return Err(ConfigError::InvalidOutputPath(key, path.clone()));
+            }
+        }
+
+        for (name, collection) in &self.collections {
+            // Expanded placeholders are checked for each item, see
+            // crate::permalink::expand
+            if let Some(permalink) = &collection.permalink
+                && permalink
+                    .split('/')
+                    .any(|segment| segment == "." || segment == "..")
+            {
+                return Err(ConfigError::InvalidOutputPath(
+                    format!("collections.{}.permalink", name),
+                    permalink.clone(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub fn create(path: &Path, features: Option<&[InitFeature]>) -> Result<(), CreateConfigError> {
+        Self::create_with_global(path, features, None)
+    }
+
+    /// Like [`Config::create`], also writing `global` data, like the site name
+    /// of a starter template
+    pub fn create_with_global(
+        path: &Path,
+        features: Option<&[InitFeature]>,
+        global: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<(), CreateConfigError> {
         let config_path = path.join("balzac.toml");
 
         if config_path.exists() {
@@ -256,13 +805,38 @@ impl Config {
 
         let features = features.unwrap_or(&[]);
 
-        let mut config = Config::default();
+        let mut config = Config {
+            global,
+            ..Default::default()
+        };
 
         if features.contains(&InitFeature::Sitemap) {
             config.base_url = Some("https://example.com".to_string());
             config.sitemap = Some(SitemapConfig::default());
         }
 
+        if features.contains(&InitFeature::Vite) {
+            config.bundler = Some(Bundler {
+                vite: Some(ViteBundler {
+                    enabled: true,
+                    manifest_path: default_vite_manifest_path(),
+                }),
+            });
+        }
+
+        if features.contains(&InitFeature::Vite) || features.contains(&InitFeature::Hooks) {
+            let mut hooks = Hooks::default();
+            if features.contains(&InitFeature::Vite) {
+                // The manifest has to exist before the renderer registers the vite helper
+                hooks.render_init_before = Some("npm run build".to_string());
+            }
+            if features.contains(&InitFeature::Hooks) {
+                hooks.build_before = Some("echo 'Starting build'".to_string());
+                hooks.build_after = Some("echo 'Build finished'".to_string());
+            }
+            config.hooks = Some(hooks);
+        }
+
         let config_content = toml::to_string_pretty(&config)?;
         fs::write(&config_path, config_content)?;
 
@@ -294,6 +868,26 @@ fn default_content_directory() -> String {
     "./content".to_string()
 }
 
+fn default_assets_mount() -> String {
+    "/assets".to_string()
+}
+
+fn default_assets_ignore() -> Vec<String> {
+    vec![
+        ".DS_Store".to_string(),
+        "Thumbs.db".to_string(),
+        "*.map".to_string(),
+    ]
+}
+
+fn default_static_to() -> String {
+    "/".to_string()
+}
+
+fn default_archetypes_directory() -> String {
+    "./archetypes".to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -312,6 +906,7 @@ mod tests {
             bundler: None,
             base_url: None,
             sitemap: None,
+            ..Default::default()
         };
         assert_eq!(config.output_directory, "./dist");
     }
@@ -330,6 +925,7 @@ mod tests {
             bundler: None,
             base_url: None,
             sitemap: None,
+            ..Default::default()
         };
         assert_eq!(config.pages_directory, "./pages");
     }
@@ -352,6 +948,7 @@ mod tests {
             bundler: None,
             base_url: None,
             sitemap: None,
+            ..Default::default()
         };
 
         assert!(config.global.is_some());
@@ -380,6 +977,7 @@ mod tests {
             bundler: None,
             base_url: None,
             sitemap: None,
+            ..Default::default()
         };
 
         assert_eq!(config.output_directory, "./build");
@@ -407,6 +1005,7 @@ mod tests {
                 default_priority: Some(0.5),
                 default_changefreq: Some("weekly".to_string()),
             }),
+            ..Default::default()
         };
 
         assert_eq!(config.base_url, Some("https://example.com".to_string()));
@@ -469,6 +1068,22 @@ mod tests {
         let _ = fs::remove_dir_all(&temp_dir);
     }
 
+    #[test]
+    fn test_config_create_with_global() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let global = HashMap::from([("site_name".to_string(), serde_json::json!("My \"Blog\""))]);
+
+        Config::create_with_global(temp_dir.path(), Some(&[InitFeature::Sitemap]), Some(global))
+            .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(
+            config.global.unwrap().get("site_name"),
+            Some(&serde_json::json!("My \"Blog\""))
+        );
+        assert!(config.sitemap.is_some());
+    }
+
     #[test]
     fn test_config_serialization_roundtrip() {
         let config = Config {
@@ -498,4 +1113,263 @@ mod tests {
         assert!(sitemap.enabled);
         assert_eq!(sitemap.default_priority, Some(0.8));
     }
+
+    #[test]
+    fn test_config_load() {
+        let temp_dir = std::env::temp_dir().join("balzac_test_load");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        assert!(matches!(Config::load(&temp_dir), Err(ConfigError::Io(_))));
+
+        fs::write(temp_dir.join("balzac.toml"), "output_directory = 42").unwrap();
+        assert!(matches!(
+            Config::load(&temp_dir),
+            Err(ConfigError::Parse(_))
+        ));
+
+        fs::write(
+            temp_dir.join("balzac.toml"),
+            "output_directory = \"./build\"",
+        )
+        .unwrap();
+        let config = Config::load(&temp_dir).unwrap();
+        assert_eq!(config.output_directory, "./build");
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_config_create_with_vite_and_hooks_features() {
+        let temp_dir = std::env::temp_dir().join("balzac_test_create_vite_hooks");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let result = Config::create(&temp_dir, Some(&[InitFeature::Vite, InitFeature::Hooks]));
+        assert!(result.is_ok());
+
+        let parsed = Config::load(&temp_dir).unwrap();
+        let vite = parsed.bundler.unwrap().vite.unwrap();
+        assert!(vite.enabled);
+        assert_eq!(vite.manifest_path, "dist/.vite/manifest.json");
+
+        let hooks = parsed.hooks.unwrap();
+        assert_eq!(hooks.render_init_before, Some("npm run build".to_string()));
+        assert!(hooks.build_before.is_some());
+        assert!(hooks.build_after.is_some());
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_config_load_with_environment() {
+        let temp_dir = std::env::temp_dir().join("balzac_test_load_environment");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        fs::write(
+            temp_dir.join("balzac.toml"),
+            "base_url = \"http://localhost\"\n\n[global]\nsite_name = \"Site\"\nanalytics_id = \"dev\"\n\n[hooks]\nbuild_before = \"echo base\"\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.join("balzac.production.toml"),
+            "base_url = \"https://example.com\"\n\n[global]\nanalytics_id = \"UA-1\"\n\n[hooks]\nbuild_after = \"echo deployed\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load_with_environment(&temp_dir, Some("production")).unwrap();
+        assert_eq!(config.environment, Some("production".to_string()));
+        assert_eq!(config.base_url, Some("https://example.com".to_string()));
+        let global = config.global.unwrap();
+        assert_eq!(global.get("site_name"), Some(&serde_json::json!("Site")));
+        assert_eq!(global.get("analytics_id"), Some(&serde_json::json!("UA-1")));
+        let hooks = config.hooks.unwrap();
+        assert_eq!(hooks.build_before, Some("echo base".to_string()));
+        assert_eq!(hooks.build_after, Some("echo deployed".to_string()));
+
+        let result = Config::load_with_environment(&temp_dir, Some("staging"));
+        assert!(
+            matches!(result, Err(ConfigError::OverlayIo(ref f, _)) if f == "balzac.staging.toml")
+        );
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_config_load_interpolates_variables() {
+        let temp_dir = std::env::temp_dir().join("balzac_test_load_interpolation");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        fs::write(
+            temp_dir.join("balzac.toml"),
+            "base_url = \"${BALZAC_TEST_UNSET_URL:-http://localhost:3000}\"\n\n[global]\nhome = \"${HOME}\"\n",
+        )
+        .unwrap();
+        let config = Config::load(&temp_dir).unwrap();
+        assert_eq!(config.base_url, Some("http://localhost:3000".to_string()));
+        assert_eq!(
+            config.global.unwrap().get("home"),
+            Some(&serde_json::json!(std::env::var("HOME").unwrap()))
+        );
+
+        fs::write(
+            temp_dir.join("balzac.toml"),
+            "[hooks]\nbuild_after = \"deploy --token ${BALZAC_TEST_UNSET_TOKEN}\"\n",
+        )
+        .unwrap();
+        let error = Config::load(&temp_dir).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Could not expand hooks.build_after: environment variable BALZAC_TEST_UNSET_TOKEN is not set"
+        );
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_url_style_locate() {
+        use std::path::PathBuf;
+
+        let locate = |style: UrlStyle, route: &str| style.locate(route);
+
+        assert_eq!(
+            locate(UrlStyle::Extensionless, "about"),
+            ("/about".to_string(), PathBuf::from("about.html"))
+        );
+        assert_eq!(
+            locate(UrlStyle::Pretty, "posts/hello"),
+            (
+                "/posts/hello/".to_string(),
+                PathBuf::from("posts/hello/index.html")
+            )
+        );
+        assert_eq!(
+            locate(UrlStyle::Html, "docs/install"),
+            (
+                "/docs/install.html".to_string(),
+                PathBuf::from("docs/install.html")
+            )
+        );
+        for style in [UrlStyle::Extensionless, UrlStyle::Pretty, UrlStyle::Html] {
+            assert_eq!(
+                locate(style, "index"),
+                ("/".to_string(), PathBuf::from("index.html"))
+            );
+            assert_eq!(
+                locate(style, "docs/index"),
+                ("/docs/".to_string(), PathBuf::from("docs/index.html"))
+            );
+        }
+
+        let config: Config = toml::from_str("url_style = \"pretty\"").unwrap();
+        assert_eq!(config.url_style, UrlStyle::Pretty);
+        assert!(
+            !toml::to_string(&Config::default())
+                .unwrap()
+                .contains("url_style")
+        );
+    }
+
+    #[test]
+    fn test_static_mounts() {
+        let config: Config = toml::from_str(
+            "[[static]]\nfrom = \"./public\"\n\n[[static]]\nfrom = \"./fonts\"\nto = \"/vendor/fonts/\"\nexclude = [\"*.ttf\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.static_mounts.len(), 2);
+        assert_eq!(config.static_mounts[0].to, "/");
+
+        let root = Path::new("/site");
+        let resolved = config.resolve(root);
+        assert_eq!(resolved.static_mounts[0].from, root.join("./public"));
+        assert_eq!(resolved.static_mounts[0].to, root.join("./dist"));
+        assert_eq!(
+            resolved.static_mounts[1].to,
+            root.join("./dist/vendor/fonts/")
+        );
+        assert_eq!(resolved.static_mounts[1].exclude, vec!["*.ttf".to_string()]);
+
+        let serialized = toml::to_string(&config).unwrap();
+        assert!(serialized.contains("[[static]]"));
+    }
+
+    #[test]
+    fn test_mounts_must_stay_inside_output() {
+        assert!(Config::default().validate().is_ok());
+
+        for (mount, valid) in [
+            ("/", true),
+            ("/vendor/fonts/", true),
+            ("./public", true),
+            ("../x", false),
+            ("/docs/../../x", false),
+            ("//etc", true),
+        ] {
+            let config: Config = toml::from_str(&format!(
+                "[[static]]\nfrom = \"./public\"\nto = \"{}\"\n",
+                mount
+            ))
+            .unwrap();
+            assert_eq!(config.validate().is_ok(), valid, "{}", mount);
+        }
+
+        let config = Config {
+            assets_mount: "/assets/../..".to_string(),
+            ..Default::default()
+        };
+        let error = config.validate().unwrap_err();
+        assert!(
+            matches!(error, ConfigError::InvalidOutputPath(ref key, _) if key == "assets_mount")
+        );
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("balzac.toml"),
+            "[[static]]\nfrom = \"./public\"\nto = \"../x\"\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            Config::load(temp_dir.path()),
+            Err(ConfigError::InvalidOutputPath(..))
+        ));
+    }
+
+    #[test]
+    fn test_collection_outputs_must_stay_inside_output() {
+        for (collection, valid) in [
+            ("output = \"blog\"", true),
+            ("output = \"/\"", true),
+            ("permalink = \"/blog/{year}/{slug}/\"", true),
+            ("output = \"../x\"", false),
+            ("output = \"/blog/../../x\"", false),
+            ("permalink = \"/../../{slug}\"", false),
+            ("permalink = \"/blog/./{slug}\"", false),
+        ] {
+            let config: Config =
+                toml::from_str(&format!("[collections.posts]\n{}\n", collection)).unwrap();
+            assert_eq!(config.validate().is_ok(), valid, "{}", collection);
+        }
+
+        let config: Config =
+            toml::from_str("[collections.posts]\npermalink = \"/../{slug}\"\n").unwrap();
+        let error = config.validate().unwrap_err();
+        assert!(
+            matches!(error, ConfigError::InvalidOutputPath(ref key, _) if key == "collections.posts.permalink")
+        );
+    }
+
+    #[test]
+    fn test_taxonomy_outputs_must_stay_inside_output() {
+        for (taxonomy, valid) in [
+            ("[taxonomies.tags]\n", true),
+            ("[taxonomies.tags]\noutput = \"/blog/tags/\"\n", true),
+            ("[taxonomies.tags]\noutput = \"../tags\"\n", false),
+            ("[taxonomies.\"..\"]\n", false),
+        ] {
+            let config: Config = toml::from_str(taxonomy).unwrap();
+            assert_eq!(config.validate().is_ok(), valid, "{}", taxonomy);
+        }
+    }
 }
@@ -1,4 +1,8 @@
+use serde_json::{Value, json};
+
 use crate::config;
+use crate::sitemap::PageEntry;
+use crate::taxonomy::Term;
 
 pub fn merge_contexts(
     configuration: &config::ResolvedConfig,
@@ -11,25 +15,157 @@ pub fn merge_contexts(
         .unwrap_or(serde_json::json!({}));
 
     let mut merged = global_value;
+    if let Some(environment) = &configuration.environment {
+        merge(
+            &mut merged,
+            &serde_json::json!({ "environment": environment }),
+        );
+    }
     merge(&mut merged, &local_context);
     merged
 }
 
+/// Replaces the fields of `context`, built by [`merge_contexts`], that
+/// `local_context` sets, the way [`merge_contexts`] would have merged them
+///
+/// The other fields are left untouched, which lets pages share a context
+/// holding large values.
+pub fn set_fields(
+    configuration: &config::ResolvedConfig,
+    context: &mut serde_json::Value,
+    local_context: serde_json::Value,
+) {
+    let keys: Vec<String> = local_context
+        .as_object()
+        .map(|fields| fields.keys().cloned().collect())
+        .unwrap_or_default();
+    let mut merged = merge_contexts(configuration, local_context);
+    for key in keys {
+        context[key.as_str()] = merged[key.as_str()].take();
+    }
+}
+
 fn merge(a: &mut serde_json::Value, b: &serde_json::Value) {
     if let (serde_json::Value::Object(a_map), serde_json::Value::Object(b_map)) = (a, b) {
         for (key, b_value) in b_map {
             match a_map.get(key) {
-                Some(a_value) => {
-                    if a_value.is_object() && b_value.is_object() {
-                        merge(a_map.get_mut(key).unwrap(), b_value);
-                    } else {
-                        a_map.insert(key.clone(), b_value.clone());
-                    }
+                Some(a_value) if a_value.is_object() && b_value.is_object() => {
+                    merge(a_map.get_mut(key).unwrap(), b_value);
                 }
-                None => {
+                _ => {
                     a_map.insert(key.clone(), b_value.clone());
                 }
             }
         }
     }
 }
+
+/// Builds the section tree of a collection from its items, so that details
+/// templates can render a sidebar of the whole collection
+///
+/// Every level has the `items` directly inside it and its sub`sections`,
+/// each with a `name`, in discovery order.
+pub fn collection_tree(name: &str, items: &[&PageEntry]) -> Value {
+    let mut tree = json!({"name": name, "items": [], "sections": []});
+
+    for item in items {
+        let mut level = &mut tree;
+        for section in &item.sections {
+            let sections = level["sections"]
+                .as_array_mut()
+                .expect("Tree level should have sections");
+            let position = match sections.iter().position(|s| s["name"] == *section) {
+                Some(position) => position,
+                None => {
+                    sections.push(json!({"name": section, "items": [], "sections": []}));
+                    sections.len() - 1
+                }
+            };
+            level = &mut level["sections"][position];
+        }
+
+        level["items"]
+            .as_array_mut()
+            .expect("Tree level should have items")
+            .push(json!({
+                "url": item.url_path,
+                "fm": item.frontmatter,
+                "sections": item.sections,
+            }));
+    }
+
+    tree
+}
+
+/// Builds the `pagination` object of page `page` (starting at 1) of a
+/// collection list, from the listed `items` and the URLs of every list page
+///
+/// `prev` and `next` are null on the first and last pages.
+pub fn pagination(
+    items: &[&PageEntry],
+    page: usize,
+    page_size: usize,
+    page_urls: &[&str],
+) -> Value {
+    let page_items: Vec<Value> = items
+        .iter()
+        .skip((page - 1) * page_size)
+        .take(page_size)
+        .map(|item| {
+            json!({
+                "url": item.url_path,
+                "fm": item.frontmatter,
+                "sections": item.sections,
+            })
+        })
+        .collect();
+    let pages: Vec<Value> = page_urls
+        .iter()
+        .enumerate()
+        .map(|(index, url)| {
+            json!({
+                "number": index + 1,
+                "url": url,
+                "current": index + 1 == page,
+            })
+        })
+        .collect();
+
+    json!({
+        "items": page_items,
+        "page": page,
+        "page_size": page_size,
+        "total_pages": page_urls.len(),
+        "total_items": items.len(),
+        "pages": pages,
+        "first": page_urls.first(),
+        "last": page_urls.last(),
+        "prev": page.checked_sub(2).and_then(|index| page_urls.get(index)),
+        "next": page_urls.get(page),
+    })
+}
+
+/// Describes a taxonomy `term` to templates, along with the `url` of its page
+/// if it has one
+pub fn taxonomy_term(term: &Term, url: Option<&str>) -> Value {
+    let items: Vec<Value> = term
+        .items
+        .iter()
+        .map(|item| {
+            json!({
+                "url": item.url_path,
+                "fm": item.frontmatter,
+                "sections": item.sections,
+                "collection": item.page_type.collection_name(),
+            })
+        })
+        .collect();
+
+    json!({
+        "name": term.name,
+        "slug": term.slug,
+        "url": url,
+        "count": items.len(),
+        "items": items,
+    })
+}
@@ -0,0 +1,115 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, Utc};
+use serde_json::Value;
+
+/// Frontmatter keys holding the publication window of a collection item
+pub const SCHEDULE_KEYS: [&str; 2] = ["publish_date", "expiry_date"];
+
+/// Parses a date as written in frontmatter or passed to `--now`
+///
+/// Accepts RFC 3339 dates (`2024-01-20T10:00:00+02:00`), and dates with
+/// (`2024-01-20T10:00:00` or `2024-01-20 10:00:00`) or without
+/// (`2024-01-20`, meaning midnight) a time, which are in local time.
+pub fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+
+    parse_naive(value)?
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Parses the calendar day of a date, as written: RFC 3339 dates keep their
+/// own offset and other dates are not converted from local time
+///
+/// `2024-03-07T00:30:00+02:00` is March 7 whatever the timezone of the
+/// machine, unlike with [`parse_date`].
+pub fn parse_day(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.date_naive());
+    }
+
+    parse_naive(value).map(|date| date.date())
+}
+
+fn parse_naive(value: &str) -> Option<NaiveDateTime> {
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+/// Reads the date stored under `key` in `frontmatter`
+///
+/// Returns `Ok(None)` if the key is missing and an error message if its value
+/// is not a valid date.
+pub fn frontmatter_date(frontmatter: &Value, key: &str) -> Result<Option<DateTime<Utc>>, String> {
+    frontmatter_value(frontmatter, key, parse_date)
+}
+
+/// Reads the calendar day stored under `key` in `frontmatter`, see
+/// [`parse_day`]
+pub fn frontmatter_day(frontmatter: &Value, key: &str) -> Result<Option<NaiveDate>, String> {
+    frontmatter_value(frontmatter, key, parse_day)
+}
+
+fn frontmatter_value<T>(
+    frontmatter: &Value,
+    key: &str,
+    parse: fn(&str) -> Option<T>,
+) -> Result<Option<T>, String> {
+    match frontmatter.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => parse(value).map(Some).ok_or_else(|| {
+            format!(
+                "invalid {} {:?}, expected YYYY-MM-DD or RFC 3339",
+                key, value
+            )
+        }),
+        Some(value) => Err(format!("invalid {} {}, expected a date string", key, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(
+            parse_date("2024-01-20T10:00:00+02:00"),
+            Some(
+                DateTime::parse_from_rfc3339("2024-01-20T08:00:00Z")
+                    .unwrap()
+                    .with_timezone(&Utc)
+            )
+        );
+        assert_eq!(parse_date("2024-01-20"), parse_date("2024-01-20T00:00:00"));
+        assert!(parse_date("2024-01-20 10:30:00").is_some());
+        assert!(parse_date("next tuesday").is_none());
+        assert!(parse_date("2024-13-01").is_none());
+    }
+
+    #[test]
+    fn test_frontmatter_date() {
+        let fm = serde_json::json!({
+            "publish_date": "2024-01-20",
+            "expiry_date": "soon",
+            "lastmod": 2024
+        });
+
+        assert!(frontmatter_date(&fm, "publish_date").unwrap().is_some());
+        assert!(frontmatter_date(&fm, "missing").unwrap().is_none());
+        assert!(frontmatter_date(&fm, "expiry_date").is_err());
+        assert!(frontmatter_date(&fm, "lastmod").is_err());
+    }
+}
@@ -1,8 +1,9 @@
 use crate::config::Hooks;
 use shell_words::split;
+use std::io;
 use std::path::Path;
 use std::process::Command;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// Represents the different phases where hooks can be executed
 #[derive(Debug, Clone, Copy)]
@@ -16,8 +17,8 @@ pub enum HookPhase {
 }
 
 impl HookPhase {
-    /// Returns the hook name for logging purposes
-    fn name(&self) -> &'static str {
+    /// Returns the hook name, as used in balzac.toml
+    pub fn name(&self) -> &'static str {
         match self {
             HookPhase::BuildBefore => "build_before",
             HookPhase::BuildAfter => "build_after",
@@ -29,6 +30,16 @@ impl HookPhase {
     }
 }
 
+/// A hook that ran successfully
+///
+/// The command is left out on purpose: once expanded it may hold secrets
+/// from the environment, and runs end up in build reports.
+#[derive(Debug, Clone)]
+pub struct HookRun {
+    pub phase: HookPhase,
+    pub duration: Duration,
+}
+
 /// Executes hooks at various phases of the build process
 pub struct HookExecutor<'a> {
     hooks: Option<&'a Hooks>,
@@ -58,17 +69,16 @@ impl<'a> HookExecutor<'a> {
 
     /// Executes a hook at the specified phase
     ///
-    /// Returns Ok(()) if:
-    /// - The hook is not configured (silent success)
-    /// - The hook executes successfully
+    /// Returns Ok(None) if the hook is not configured (silent success), and
+    /// Ok(Some(run)) with its timing if the hook executes successfully
     ///
-    /// Exits the process with code 1 if:
+    /// Returns an error if:
     /// - The hook command is invalid or empty
     /// - The hook process fails to start
     /// - The hook process exits with non-zero status
-    pub fn execute(&self, phase: HookPhase) {
+    pub fn execute(&self, phase: HookPhase) -> io::Result<Option<HookRun>> {
         let Some(hook_command) = self.get_hook_command(phase) else {
-            return; // Hook not configured, silent success
+            return Ok(None); // Hook not configured, silent success
         };
 
         let hook_name = phase.name();
@@ -76,10 +86,17 @@ impl<'a> HookExecutor<'a> {
         let start = Instant::now();
 
         // Parse command
-        let parts = split(hook_command).expect("Invalid command syntax");
+        let parts = split(hook_command).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} hook has invalid command syntax: {}", hook_name, e),
+            )
+        })?;
         if parts.is_empty() {
-            log::error!("{} hook is empty", hook_name);
-            std::process::exit(1);
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} hook is empty", hook_name),
+            ));
         }
 
         // Build command
@@ -90,24 +107,20 @@ impl<'a> HookExecutor<'a> {
         cmd.current_dir(self.base_path);
 
         // Execute and check status
-        match cmd.status() {
-            Ok(status) => {
-                if !status.success() {
-                    log::error!(
-                        "{} hook failed with exit code: {:?}",
-                        hook_name,
-                        status.code()
-                    );
-                    std::process::exit(1);
-                }
-            }
-            Err(e) => {
-                log::error!("Error running {} hook: {}", hook_name, e);
-                std::process::exit(1);
-            }
+        let status = cmd.status().map_err(|e| {
+            io::Error::new(e.kind(), format!("Error running {} hook: {}", hook_name, e))
+        })?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "{} hook failed with exit code: {:?}",
+                hook_name,
+                status.code()
+            )));
         }
 
-        log::info!("{} hook completed (took {:?})", hook_name, start.elapsed());
+        let duration = start.elapsed();
+        log::info!("{} hook completed (took {:?})", hook_name, duration);
+        Ok(Some(HookRun { phase, duration }))
     }
 }
 
@@ -132,13 +145,23 @@ mod tests {
         let base_path = PathBuf::from("/tmp");
         let executor = HookExecutor::new(None, &base_path);
 
-        // Should not panic or exit
-        executor.execute(HookPhase::BuildBefore);
-        executor.execute(HookPhase::BuildAfter);
-        executor.execute(HookPhase::RenderInitBefore);
-        executor.execute(HookPhase::RenderInitAfter);
-        executor.execute(HookPhase::RenderBefore);
-        executor.execute(HookPhase::RenderAfter);
+        // Should succeed without running anything
+        assert!(executor.execute(HookPhase::BuildBefore).unwrap().is_none());
+        assert!(executor.execute(HookPhase::BuildAfter).unwrap().is_none());
+        assert!(
+            executor
+                .execute(HookPhase::RenderInitBefore)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            executor
+                .execute(HookPhase::RenderInitAfter)
+                .unwrap()
+                .is_none()
+        );
+        assert!(executor.execute(HookPhase::RenderBefore).unwrap().is_none());
+        assert!(executor.execute(HookPhase::RenderAfter).unwrap().is_none());
     }
 
     #[test]
@@ -213,12 +236,53 @@ mod tests {
         let base_path = PathBuf::from("/tmp");
         let executor = HookExecutor::new(Some(&hooks), &base_path);
 
-        // Should not panic or exit
-        executor.execute(HookPhase::BuildBefore);
-        executor.execute(HookPhase::BuildAfter);
-        executor.execute(HookPhase::RenderInitBefore);
-        executor.execute(HookPhase::RenderInitAfter);
-        executor.execute(HookPhase::RenderBefore);
-        executor.execute(HookPhase::RenderAfter);
+        // Should succeed without running anything
+        assert!(executor.execute(HookPhase::BuildBefore).unwrap().is_none());
+        assert!(executor.execute(HookPhase::BuildAfter).unwrap().is_none());
+        assert!(
+            executor
+                .execute(HookPhase::RenderInitBefore)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            executor
+                .execute(HookPhase::RenderInitAfter)
+                .unwrap()
+                .is_none()
+        );
+        assert!(executor.execute(HookPhase::RenderBefore).unwrap().is_none());
+        assert!(executor.execute(HookPhase::RenderAfter).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_execute_failing_hook_returns_error() {
+        let hooks = Hooks {
+            build_before: Some("false".to_string()),
+            build_after: Some("   ".to_string()),
+            render_init_before: Some("echo 'unterminated".to_string()),
+            render_init_after: None,
+            render_before: None,
+            render_after: None,
+        };
+        let base_path = PathBuf::from("/tmp");
+        let executor = HookExecutor::new(Some(&hooks), &base_path);
+
+        assert!(executor.execute(HookPhase::BuildBefore).is_err());
+        assert!(executor.execute(HookPhase::BuildAfter).is_err());
+        assert!(executor.execute(HookPhase::RenderInitBefore).is_err());
+    }
+
+    #[test]
+    fn test_execute_returns_hook_run() {
+        let hooks = Hooks {
+            build_before: Some("true".to_string()),
+            ..Default::default()
+        };
+        let base_path = PathBuf::from("/tmp");
+        let executor = HookExecutor::new(Some(&hooks), &base_path);
+
+        let run = executor.execute(HookPhase::BuildBefore).unwrap().unwrap();
+        assert_eq!(run.phase.name(), "build_before");
     }
 }
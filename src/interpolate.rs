@@ -0,0 +1,129 @@
+/// Error returned when a string can not be interpolated
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpolationError {
+    /// A variable without a default value is not set
+    Unset(String),
+    /// A `${` is never closed
+    Unterminated,
+}
+
+impl std::fmt::Display for InterpolationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterpolationError::Unset(name) => {
+                write!(f, "environment variable {} is not set", name)
+            }
+            InterpolationError::Unterminated => write!(f, "unterminated ${{ in value"),
+        }
+    }
+}
+
+impl std::error::Error for InterpolationError {}
+
+/// Expands `${VAR}` and `${VAR:-default}` references in `input` using the
+/// process environment
+pub fn interpolate_env(input: &str) -> Result<String, InterpolationError> {
+    interpolate(input, |name| std::env::var(name).ok())
+}
+
+/// Expands `${VAR}` and `${VAR:-default}` references in `input`, looking
+/// variables up with `lookup`
+///
+/// As in the shell, the default is used when the variable is unset or empty.
+/// `$${` produces a literal `${`.
+pub fn interpolate<F>(input: &str, lookup: F) -> Result<String, InterpolationError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(escaped) = rest.strip_prefix("$${") {
+            output.push_str("${");
+            rest = escaped;
+            continue;
+        }
+
+        let Some(reference) = rest.strip_prefix("${") else {
+            output.push('$');
+            rest = &rest[1..];
+            continue;
+        };
+
+        let end = reference
+            .find('}')
+            .ok_or(InterpolationError::Unterminated)?;
+        let (name, default) = match reference[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&reference[..end], None),
+        };
+
+        match (lookup(name), default) {
+            (Some(value), Some(default)) if value.is_empty() => output.push_str(default),
+            (Some(value), _) => output.push_str(&value),
+            (None, Some(default)) => output.push_str(default),
+            (None, None) => return Err(InterpolationError::Unset(name.to_string())),
+        }
+        rest = &reference[end + 1..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "DEPLOY_URL" => Some("https://example.com".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_interpolate_variables() {
+        assert_eq!(
+            interpolate("${DEPLOY_URL}/blog", lookup).unwrap(),
+            "https://example.com/blog"
+        );
+        assert_eq!(interpolate("no variables", lookup).unwrap(), "no variables");
+        assert_eq!(interpolate("costs $5", lookup).unwrap(), "costs $5");
+        assert_eq!(
+            interpolate("$${DEPLOY_URL}", lookup).unwrap(),
+            "${DEPLOY_URL}"
+        );
+    }
+
+    #[test]
+    fn test_interpolate_defaults() {
+        assert_eq!(
+            interpolate("${MISSING:-http://localhost:3000}", lookup).unwrap(),
+            "http://localhost:3000"
+        );
+        assert_eq!(
+            interpolate("${EMPTY:-fallback}", lookup).unwrap(),
+            "fallback"
+        );
+        assert_eq!(interpolate("${EMPTY}", lookup).unwrap(), "");
+        assert_eq!(interpolate("${MISSING:-}", lookup).unwrap(), "");
+    }
+
+    #[test]
+    fn test_interpolate_errors() {
+        assert_eq!(
+            interpolate("${MISSING}", lookup),
+            Err(InterpolationError::Unset("MISSING".to_string()))
+        );
+        assert_eq!(
+            interpolate("${DEPLOY_URL", lookup),
+            Err(InterpolationError::Unterminated)
+        );
+    }
+}
@@ -1,21 +1,56 @@
+pub mod archetype;
+pub mod assets;
+pub mod build;
+pub mod cache;
+pub mod check;
 pub mod cli;
 pub mod collection;
 pub mod config;
 pub mod context;
+pub mod date;
 pub mod hooks;
+pub mod interpolate;
+pub mod output;
+pub mod permalink;
 pub mod renderer;
+pub mod report;
+pub mod routes;
+pub mod serve;
 pub mod sitemap;
+pub mod starter;
+pub mod taxonomy;
 pub mod vite;
+pub mod watch;
 
-use std::{fs, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use chrono::Utc;
+use rayon::prelude::*;
 
 use crate::{
     context::merge_contexts,
     renderer::{HandlebarsRenderer, Renderer},
+    report::{Diagnostics, RenderedPage},
     sitemap::{PageEntry, PageType, SitePages, SitemapMeta},
 };
 
+/// Deletes and recreates the output directory
+///
+/// Fails without deleting anything if the output directory is not safe to
+/// delete, see [`output::ensure_safe_to_clean`].
 pub fn make_dist_folder(parsed_config: &config::ResolvedConfig) -> std::io::Result<()> {
+    output::ensure_safe_to_clean(parsed_config)?;
+    make_dist_folder_unchecked(parsed_config)
+}
+
+/// Same as [`make_dist_folder`], without making sure that the output directory
+/// is safe to delete
+pub fn make_dist_folder_unchecked(parsed_config: &config::ResolvedConfig) -> std::io::Result<()> {
     let dir_exists = fs::exists(&parsed_config.output_directory)?;
     if !dir_exists {
         log::debug!(
@@ -34,41 +69,81 @@ pub fn make_dist_folder(parsed_config: &config::ResolvedConfig) -> std::io::Resu
     Ok(())
 }
 
-pub fn add_assets(parsed_config: &config::ResolvedConfig) -> std::io::Result<()> {
+/// Copies assets and static directories into the output directory,
+/// returning the copied files
+///
+/// See [`static_files`]. Files whose content did not change are left
+/// untouched.
+pub fn add_assets(parsed_config: &config::ResolvedConfig) -> std::io::Result<Vec<PathBuf>> {
+    let mut copied = Vec::new();
+    assets::copy_files(&static_files(parsed_config)?, &mut copied)?;
+    Ok(copied)
+}
+
+/// Lists the files of the assets directory and of the static directories,
+/// along with the path they are copied to
+///
+/// The assets directory is copied recursively to the assets mount point,
+/// skipping ignored files, then every `[[static]]` directory is copied to its
+/// own mount point.
+pub fn static_files(
+    parsed_config: &config::ResolvedConfig,
+) -> std::io::Result<Vec<assets::CopiedFile>> {
+    if let Some(mount) = parsed_config
+        .static_mounts
+        .iter()
+        .find(|mount| !mount.from.is_dir())
+    {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Static directory {} does not exist", mount.from.display()),
+        ));
+    }
+    existing_static_files(parsed_config)
+}
+
+/// Same as [`static_files`], leaving out the static directories that do not
+/// exist instead of failing
+pub fn existing_static_files(
+    parsed_config: &config::ResolvedConfig,
+) -> std::io::Result<Vec<assets::CopiedFile>> {
+    let mut files = Vec::new();
     let dir_exists = fs::exists(&parsed_config.assets_directory)?;
 
     if dir_exists {
-        fs::create_dir(parsed_config.output_directory.join("assets"))?;
-        for entry in fs::read_dir(&parsed_config.assets_directory)? {
-            let dir = entry?;
-
-            let is_directory = dir.metadata()?.is_dir();
-            if is_directory {
-                log::debug!(
-                    "entry {} is a directory, skipping",
-                    dir.file_name().to_string_lossy()
-                );
-                continue;
-            }
+        let filter = assets::CopyFilter::new(&[], &parsed_config.assets_ignore)?;
+        files.extend(assets::list_tree(
+            &parsed_config.assets_directory,
+            &parsed_config.assets_output_directory,
+            &filter,
+        )?);
+    } else {
+        log::debug!("Assets directory does not exist, skipping");
+    }
 
-            let path = parsed_config
-                .output_directory
-                .join("assets")
-                .join(dir.file_name());
+    for mount in &parsed_config.static_mounts {
+        if !mount.from.is_dir() {
             log::debug!(
-                "Copying {} to {}",
-                dir.file_name().to_string_lossy(),
-                path.display()
+                "Static directory {} does not exist, skipping",
+                mount.from.display()
             );
-            fs::copy(dir.path(), path)?;
+            continue;
         }
-    } else {
-        log::debug!("Assets directory does not exist, skipping");
+
+        let filter = assets::CopyFilter::new(&mount.include, &mount.exclude)?;
+        files.extend(assets::list_tree(&mount.from, &mount.to, &filter)?);
     }
 
-    Ok(())
+    Ok(files)
 }
 
+/// Discovers the pages of the pages directory and of its subdirectories,
+/// mirroring the directory structure into URLs and output paths
+///
+/// The templates of collections and taxonomies are not pages of their own and
+/// are skipped, see [`template_paths`]. So are the `details.hbs` and
+/// `list.hbs` files of any subdirectory holding a `details.hbs`, which is the
+/// template directory of a collection even before it has content.
 pub fn discover_static_pages(
     parsed_config: &config::ResolvedConfig,
 ) -> std::io::Result<Vec<PageEntry>> {
@@ -80,18 +155,76 @@ pub fn discover_static_pages(
         return Ok(pages);
     }
 
-    for entry in fs::read_dir(&parsed_config.pages_directory)? {
-        let dir = entry?;
+    let templates = template_paths(parsed_config)?;
+    discover_pages_in(
+        parsed_config,
+        &parsed_config.pages_directory,
+        &templates,
+        &mut pages,
+    )?;
+
+    Ok(pages)
+}
+
+/// Templates that pages are generated from rather than pages of their own:
+/// the details and list templates of every collection, that is every
+/// directory of the content directory and every `[collections.<name>]` table,
+/// and the term and list templates of every `[taxonomies.<name>]` table
+pub fn template_paths(parsed_config: &config::ResolvedConfig) -> std::io::Result<HashSet<PathBuf>> {
+    let mut collection_names: BTreeSet<String> =
+        parsed_config.collections.keys().cloned().collect();
+    if fs::exists(&parsed_config.content_directory)? {
+        for entry in sorted_entries(&parsed_config.content_directory)? {
+            if entry.metadata()?.is_dir() {
+                collection_names.insert(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+
+    Ok(collection_names
+        .iter()
+        .flat_map(|name| {
+            [
+                parsed_config.details_page_path(name),
+                parsed_config.list_page_path(name),
+            ]
+        })
+        .chain(
+            parsed_config
+                .taxonomies
+                .values()
+                .flat_map(|taxonomy| [taxonomy.term.clone(), taxonomy.list.clone()]),
+        )
+        .collect())
+}
+
+fn discover_pages_in(
+    parsed_config: &config::ResolvedConfig,
+    directory: &Path,
+    templates: &HashSet<PathBuf>,
+    pages: &mut Vec<PageEntry>,
+) -> std::io::Result<()> {
+    let template_directory =
+        directory != parsed_config.pages_directory && fs::exists(directory.join("details.hbs"))?;
+
+    for dir in sorted_entries(directory)? {
+        let entry_path = dir.path();
+
         if dir.metadata()?.is_dir() {
+            discover_pages_in(parsed_config, &entry_path, templates, pages)?;
+            continue;
+        }
+
+        let directory_template = template_directory
+            && (dir.file_name() == "details.hbs" || dir.file_name() == "list.hbs");
+        if directory_template || templates.contains(&entry_path) {
             log::debug!(
-                "Skipping directory {} during discovery",
-                dir.file_name().to_string_lossy()
+                "Skipping template {} during discovery",
+                entry_path.display()
             );
             continue;
         }
 
-        let entry_path = dir.path();
-
         let extension = entry_path
             .extension()
             .map(|e| e.to_string_lossy().to_string());
@@ -107,21 +240,15 @@ pub fn discover_static_pages(
             continue;
         }
 
-        let file_stem = entry_path
-            .file_stem()
-            .expect("Could not get file stem")
-            .to_string_lossy()
-            .to_string();
-
-        let url_path = if file_stem == "index" {
-            "/".to_string()
-        } else {
-            format!("/{}", file_stem)
-        };
-
-        let output_path = parsed_config
-            .output_directory
-            .join(PathBuf::from(&file_stem).with_extension("html"));
+        let route: Vec<String> = entry_path
+            .strip_prefix(&parsed_config.pages_directory)
+            .expect("Page should be inside the pages directory")
+            .with_extension("")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        let (url_path, relative_output) = parsed_config.url_style.locate(&route.join("/"));
+        let output_path = parsed_config.output_directory.join(relative_output);
 
         log::debug!(
             "Discovered static page: {} -> {}",
@@ -137,16 +264,46 @@ pub fn discover_static_pages(
             sitemap_meta: SitemapMeta::default(),
             frontmatter: None,
             content: None,
+            draft: false,
+            sections: Vec::new(),
         });
     }
 
-    Ok(pages)
+    Ok(())
 }
 
 pub fn discover_collections(
     parsed_config: &config::ResolvedConfig,
+) -> std::io::Result<Vec<PageEntry>> {
+    discover_collections_with_diagnostics(parsed_config, &mut Diagnostics::default())
+}
+
+/// Same as [`discover_collections`], recording the entries that were skipped
+/// into `diagnostics`
+pub fn discover_collections_with_diagnostics(
+    parsed_config: &config::ResolvedConfig,
+    diagnostics: &mut Diagnostics,
+) -> std::io::Result<Vec<PageEntry>> {
+    discover_collections_in(parsed_config, diagnostics, false)
+}
+
+/// Same as [`discover_collections_with_diagnostics`], also skipping the items
+/// that cannot be discovered, like items with invalid frontmatter, instead of
+/// failing
+pub fn discover_valid_collections(
+    parsed_config: &config::ResolvedConfig,
+    diagnostics: &mut Diagnostics,
+) -> std::io::Result<Vec<PageEntry>> {
+    discover_collections_in(parsed_config, diagnostics, true)
+}
+
+fn discover_collections_in(
+    parsed_config: &config::ResolvedConfig,
+    diagnostics: &mut Diagnostics,
+    skip_invalid: bool,
 ) -> std::io::Result<Vec<PageEntry>> {
     let mut pages = Vec::new();
+    let now = parsed_config.now.unwrap_or_else(Utc::now);
 
     let dir_exists = fs::exists(&parsed_config.content_directory)?;
     if !dir_exists {
@@ -154,13 +311,11 @@ pub fn discover_collections(
         return Ok(pages);
     }
 
-    for entry in fs::read_dir(&parsed_config.content_directory)? {
-        let dir = entry?;
-
+    for dir in sorted_entries(&parsed_config.content_directory)? {
         if dir.metadata()?.is_file() {
-            log::warn!(
-                "Entry {} is a file; this is not allowed in content directory, skipping",
-                dir.file_name().to_string_lossy()
+            diagnostics.warn_skip(
+                &dir.path(),
+                "files are not allowed at the top level of the content directory",
             );
             continue;
         }
@@ -168,109 +323,404 @@ pub fn discover_collections(
         let collection_name = dir.file_name().to_string_lossy().to_string();
         log::debug!("Discovering collection: {}", collection_name);
 
-        let details_page_path = parsed_config
-            .pages_directory
-            .join(&collection_name)
-            .join("details.hbs");
+        let details_page_path = parsed_config.details_page_path(&collection_name);
         let has_details_page = fs::exists(&details_page_path)?;
 
         if !has_details_page {
-            log::warn!(
-                "Collection {} has no details page, skipping",
-                collection_name
+            diagnostics.warn_skip(
+                &dir.path(),
+                format!(
+                    "collection {} has no details page, expected {}",
+                    collection_name,
+                    details_page_path.display()
+                ),
             );
             continue;
         }
 
         let content_dir_path = parsed_config.content_directory.join(&collection_name);
 
-        for content_entry in fs::read_dir(&content_dir_path)? {
-            let content_file = content_entry?;
-            let content_file_path = content_file.path();
+        let mut content_files = Vec::new();
+        collect_markdown_files(&content_dir_path, &mut content_files)?;
 
-            let extension = content_file_path.extension();
-            if extension.map(|e| e.to_string_lossy()) != Some("md".into()) {
-                log::debug!(
-                    "Skipping non-markdown file {} during discovery",
-                    content_file.file_name().to_string_lossy()
+        // Markdown parsing is the expensive part of discovery, so files are
+        // parsed in parallel; collect keeps the items in file path order
+        let items: Vec<(PathBuf, std::io::Result<PageEntry>)> = content_files
+            .into_par_iter()
+            .map(|content_file_path| {
+                let item = discover_collection_item(
+                    parsed_config,
+                    &collection_name,
+                    content_file_path.clone(),
                 );
+                (content_file_path, item)
+            })
+            .collect();
+
+        let mut item_count = 0;
+        for (content_file_path, item) in items {
+            let item = match item {
+                Ok(item) => item,
+                Err(e) if skip_invalid => {
+                    diagnostics.warn_skip(&content_file_path, e.to_string());
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if item.draft && !parsed_config.drafts {
+                diagnostics.skip(&item.source_path, "draft");
+                continue;
+            }
+            if !item.sitemap_meta.is_live(now) {
+                let reason = match item.sitemap_meta.publish_date {
+                    Some(publish_date) if now < publish_date => {
+                        format!("scheduled for {}", publish_date.to_rfc3339())
+                    }
+                    _ => "expired".to_string(),
+                };
+                diagnostics.skip(&item.source_path, reason);
                 continue;
             }
+            pages.push(item);
+            item_count += 1;
+        }
 
-            let file_stem = content_file_path
-                .file_stem()
-                .expect("Could not get collection entry file stem")
-                .to_string_lossy()
-                .to_string();
+        let list_page_path = parsed_config.list_page_path(&collection_name);
+        if fs::exists(&list_page_path)? {
+            pages.extend(collection_list_pages(
+                parsed_config,
+                &collection_name,
+                list_page_path,
+                item_count,
+            ));
+        }
+    }
 
-            let file_content = fs::read_to_string(&content_file_path)?;
-            let parsed_content = collection::parse_markdown(&file_content)?;
+    Ok(pages)
+}
 
-            let sitemap_meta = SitemapMeta::from_frontmatter(&parsed_content.fm);
+/// Pages listing the `item_count` items of the collection `collection_name`,
+/// served at `/<output>/` for the first one and `/<output>/page/<n>/` for the
+/// others
+///
+/// A collection without items still gets a first, empty, list page.
+fn collection_list_pages(
+    parsed_config: &config::ResolvedConfig,
+    collection_name: &str,
+    list_page_path: PathBuf,
+    item_count: usize,
+) -> Vec<PageEntry> {
+    let collection_config = parsed_config.collection(collection_name);
+    let output = collection_config
+        .output
+        .as_deref()
+        .unwrap_or(collection_name)
+        .trim_matches('/');
+    let page_count = item_count.div_ceil(collection_config.page_size()).max(1);
 
-            let url_path = format!("/{}/{}", collection_name, file_stem);
+    (1..=page_count)
+        .map(|page| {
+            let number = page.to_string();
+            let route: &[&str] = if page == 1 {
+                &[output]
+            } else {
+                &[output, "page", &number]
+            };
+            generated_page(
+                parsed_config,
+                route,
+                list_page_path.clone(),
+                PageType::CollectionList {
+                    name: collection_name.to_string(),
+                    page,
+                },
+            )
+        })
+        .collect()
+}
 
-            let output_path = parsed_config
-                .output_directory
-                .join(&collection_name)
-                .join(&file_stem)
-                .with_extension("html");
+/// Discovers the pages of every taxonomy from the `collection_items` they
+/// classify: an index page at `/<output>/` and a page per term at
+/// `/<output>/<term>/`, each only if its template exists
+pub fn discover_taxonomies(
+    parsed_config: &config::ResolvedConfig,
+    collection_items: &[PageEntry],
+) -> std::io::Result<Vec<PageEntry>> {
+    let mut pages = Vec::new();
 
-            log::debug!(
-                "Discovered collection item: {} -> {}",
-                url_path,
-                output_path.display()
-            );
+    for (taxonomy_name, taxonomy_config) in &parsed_config.taxonomies {
+        log::debug!("Discovering taxonomy: {}", taxonomy_name);
 
-            pages.push(PageEntry {
-                url_path,
-                source_path: content_file_path,
-                output_path,
-                page_type: PageType::Collection {
-                    name: collection_name.clone(),
+        let items = taxonomy_items(taxonomy_config, collection_items.iter());
+        for item in &items {
+            if let Some(frontmatter) = &item.frontmatter {
+                taxonomy::item_terms(frontmatter, &taxonomy_config.field).map_err(|e| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("{}: {}", item.source_path.display(), e),
+                    )
+                })?;
+            }
+        }
+
+        let output = taxonomy_config.output.trim_matches('/');
+        if fs::exists(&taxonomy_config.list)? {
+            pages.push(generated_page(
+                parsed_config,
+                &[output],
+                taxonomy_config.list.clone(),
+                PageType::TaxonomyList {
+                    taxonomy: taxonomy_name.clone(),
                 },
-                sitemap_meta,
-                frontmatter: Some(parsed_content.fm),
-                content: Some(parsed_content.content),
-            });
+            ));
+        }
+        if fs::exists(&taxonomy_config.term)? {
+            for term in taxonomy::group_terms(&items, &taxonomy_config.field) {
+                pages.push(generated_page(
+                    parsed_config,
+                    &[output, &term.slug],
+                    taxonomy_config.term.clone(),
+                    PageType::TaxonomyTerm {
+                        taxonomy: taxonomy_name.clone(),
+                        term: term.slug.clone(),
+                    },
+                ));
+            }
         }
     }
 
     Ok(pages)
 }
 
-pub fn render_pages(
+/// Collection items of `pages` classified in the taxonomy `taxonomy_config`
+fn taxonomy_items<'a>(
+    taxonomy_config: &config::ResolvedTaxonomyConfig,
+    pages: impl Iterator<Item = &'a PageEntry>,
+) -> Vec<&'a PageEntry> {
+    pages
+        .filter(|page| match &page.page_type {
+            PageType::Collection { name } => taxonomy_config.classifies(name),
+            _ => false,
+        })
+        .collect()
+}
+
+/// A page that has no source file of its own, rendered from the template
+/// `source_path` and served at the directory URL made of `route` segments
+fn generated_page(
     parsed_config: &config::ResolvedConfig,
-    pages: &[PageEntry],
-    render: &HandlebarsRenderer,
-) -> std::io::Result<()> {
-    for page in pages {
-        if !matches!(page.page_type, PageType::Static) {
+    route: &[&str],
+    source_path: PathBuf,
+    page_type: PageType,
+) -> PageEntry {
+    let mut permalink = String::from("/");
+    for segment in route.iter().filter(|segment| !segment.is_empty()) {
+        permalink.push_str(segment);
+        permalink.push('/');
+    }
+    let (url_path, relative_output) = permalink::locate(&permalink, parsed_config.url_style);
+    let output_path = parsed_config.output_directory.join(relative_output);
+
+    log::debug!(
+        "Discovered {} page: {} -> {}",
+        page_type.kind(),
+        url_path,
+        output_path.display()
+    );
+
+    PageEntry {
+        url_path,
+        source_path,
+        output_path,
+        page_type,
+        sitemap_meta: SitemapMeta::default(),
+        frontmatter: None,
+        content: None,
+        draft: false,
+        sections: Vec::new(),
+    }
+}
+
+/// Collects the markdown files of a collection directory and of its
+/// subdirectories (the sections of the collection), in file path order
+fn collect_markdown_files(directory: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for content_file in sorted_entries(directory)? {
+        let content_file_path = content_file.path();
+
+        if content_file.metadata()?.is_dir() {
+            collect_markdown_files(&content_file_path, files)?;
             continue;
         }
 
-        log::info!(
-            "Rendering page {}",
-            page.source_path
-                .file_name()
-                .map(|f| f.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unknown".to_string())
-        );
+        let extension = content_file_path.extension();
+        if extension.map(|e| e.to_string_lossy()) != Some("md".into()) {
+            log::debug!(
+                "Skipping non-markdown file {} during discovery",
+                content_file.file_name().to_string_lossy()
+            );
+            continue;
+        }
 
-        let content = fs::read_to_string(&page.source_path)?;
-        let rendered = render.render(content, serde_json::json!(&parsed_config.global));
-        fs::write(&page.output_path, rendered)?;
+        files.push(content_file_path);
     }
-
     Ok(())
 }
 
+/// Folders between the directory of the collection `collection_name` and
+/// `content_file_path`, outermost first
+pub(crate) fn item_sections(
+    parsed_config: &config::ResolvedConfig,
+    collection_name: &str,
+    content_file_path: &Path,
+) -> Vec<String> {
+    content_file_path
+        .parent()
+        .and_then(|parent| {
+            parent
+                .strip_prefix(parsed_config.content_directory.join(collection_name))
+                .ok()
+        })
+        .map(|relative| {
+            relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn discover_collection_item(
+    parsed_config: &config::ResolvedConfig,
+    collection_name: &str,
+    content_file_path: PathBuf,
+) -> std::io::Result<PageEntry> {
+    let file_stem = content_file_path
+        .file_stem()
+        .expect("Could not get collection entry file stem")
+        .to_string_lossy()
+        .to_string();
+
+    let file_content = fs::read_to_string(&content_file_path)?;
+    let parsed_content = collection::parse_markdown(&file_content)?;
+
+    let invalid_data = |e: String| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{}: {}", content_file_path.display(), e),
+        )
+    };
+    for key in date::SCHEDULE_KEYS {
+        date::frontmatter_date(&parsed_content.fm, key).map_err(invalid_data)?;
+    }
+    let sitemap_meta = SitemapMeta::from_frontmatter(&parsed_content.fm);
+    let draft = parsed_content.fm.get("draft") == Some(&serde_json::Value::Bool(true));
+
+    let sections = item_sections(parsed_config, collection_name, &content_file_path);
+    let slug = permalink::item_slug(&parsed_content.fm, &file_stem).map_err(invalid_data)?;
+    let url = permalink::item_url(&parsed_content.fm).map_err(invalid_data)?;
+
+    let collection_config = parsed_config.collection(collection_name);
+    let (url_path, relative_output) = match (url, &collection_config.permalink) {
+        (Some(url), _) => permalink::locate(&url, parsed_config.url_style),
+        (None, Some(pattern)) => {
+            let fields = permalink::PermalinkFields {
+                collection: collection_name,
+                slug: &slug,
+                sections: &sections,
+                frontmatter: &parsed_content.fm,
+            };
+            let expanded = permalink::expand(pattern, &fields).map_err(invalid_data)?;
+            permalink::locate(&expanded, parsed_config.url_style)
+        }
+        (None, None) => {
+            let output = collection_config
+                .output
+                .as_deref()
+                .unwrap_or(collection_name)
+                .trim_matches('/');
+            let route: Vec<&str> = std::iter::once(output)
+                .filter(|output| !output.is_empty())
+                .chain(sections.iter().map(String::as_str))
+                .chain(std::iter::once(slug.as_str()))
+                .collect();
+            parsed_config.url_style.locate(&route.join("/"))
+        }
+    };
+    let output_path = parsed_config.output_directory.join(relative_output);
+
+    log::debug!(
+        "Discovered collection item: {} -> {}",
+        url_path,
+        output_path.display()
+    );
+
+    Ok(PageEntry {
+        url_path,
+        source_path: content_file_path,
+        output_path,
+        page_type: PageType::Collection {
+            name: collection_name.to_string(),
+        },
+        sitemap_meta,
+        frontmatter: Some(parsed_content.fm),
+        content: Some(parsed_content.content),
+        draft,
+        sections,
+    })
+}
+
+pub fn render_pages(
+    parsed_config: &config::ResolvedConfig,
+    pages: &[PageEntry],
+    render: &HandlebarsRenderer,
+) -> std::io::Result<Vec<RenderedPage>> {
+    pages
+        .par_iter()
+        .filter(|page| matches!(page.page_type, PageType::Static))
+        .map(|page| {
+            let start = Instant::now();
+            log::info!(
+                "Rendering page {}",
+                page.source_path
+                    .file_name()
+                    .map(|f| f.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "unknown".to_string())
+            );
+
+            let content = fs::read_to_string(&page.source_path)?;
+            let rendered = render.render(
+                content,
+                merge_contexts(parsed_config, serde_json::json!({})),
+            )?;
+            if let Some(parent) = page.output_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            output::write_if_changed(&page.output_path, rendered)?;
+            Ok(RenderedPage {
+                output_path: page.output_path.clone(),
+                duration: start.elapsed(),
+            })
+        })
+        .collect()
+}
+
+/// Renders the collection items of `pages` with the details template of
+/// their collection
+///
+/// `site_pages` holds every page of the site, so that the section tree given
+/// to templates of collections with `tree` enabled lists the whole collection
+/// even when only some of its items are rendered.
 pub fn render_collection_items(
     parsed_config: &config::ResolvedConfig,
     pages: &[PageEntry],
+    site_pages: &SitePages,
     render: &HandlebarsRenderer,
-) -> std::io::Result<()> {
-    let mut created_dirs: std::collections::HashSet<String> = std::collections::HashSet::new();
+) -> std::io::Result<Vec<RenderedPage>> {
+    // Details templates are read and contexts holding the section tree built
+    // once per collection up front, so that items can then be rendered in
+    // parallel without copying the tree for each of them
+    let mut collections: BTreeMap<&str, (String, handlebars::Context, Vec<&PageEntry>)> =
+        BTreeMap::new();
 
     for page in pages {
         let collection_name = match &page.page_type {
@@ -278,52 +728,288 @@ pub fn render_collection_items(
             _ => continue,
         };
 
-        if !created_dirs.contains(collection_name) {
-            let collection_output_dir = parsed_config.output_directory.join(collection_name);
-            if !fs::exists(&collection_output_dir)? {
-                fs::create_dir(&collection_output_dir)?;
-            }
-            created_dirs.insert(collection_name.clone());
+        if !collections.contains_key(collection_name.as_str()) {
             log::info!("Rendering collection {}", collection_name);
+
+            let details_page_path = parsed_config.details_page_path(collection_name);
+            let shared = if parsed_config.collection(collection_name).tree {
+                serde_json::json!({
+                    "collection": context::collection_tree(
+                        collection_name,
+                        &site_pages.collection(collection_name),
+                    ),
+                })
+            } else {
+                serde_json::json!({})
+            };
+            collections.insert(
+                collection_name,
+                (
+                    fs::read_to_string(&details_page_path)?,
+                    merge_contexts(parsed_config, shared).into(),
+                    Vec::new(),
+                ),
+            );
         }
+        collections
+            .get_mut(collection_name.as_str())
+            .expect("Collection should have been added")
+            .2
+            .push(page);
+    }
 
-        let details_page_path = parsed_config
-            .pages_directory
-            .join(collection_name)
-            .join("details.hbs");
+    let mut rendered = Vec::new();
+    for (template, shared_context, items) in collections.values() {
+        let collection_rendered = items
+            .par_iter()
+            // Each thread updates its own copy of the shared context
+            .map_init(
+                || shared_context.clone(),
+                |item_context, page| {
+                    let start = Instant::now();
 
-        let content = page
-            .content
-            .as_ref()
-            .expect("Collection item should have parsed content");
-        let frontmatter = page
-            .frontmatter
-            .as_ref()
-            .expect("Collection item should have frontmatter");
+                    let content = page
+                        .content
+                        .as_ref()
+                        .expect("Collection item should have parsed content");
+                    let frontmatter = page
+                        .frontmatter
+                        .as_ref()
+                        .expect("Collection item should have frontmatter");
 
-        let rendered_result = render.render(
-            fs::read_to_string(&details_page_path)?,
-            merge_contexts(
-                parsed_config,
-                serde_json::json!({"content": content, "fm": frontmatter}),
-            ),
-        );
+                    context::set_fields(
+                        parsed_config,
+                        item_context.data_mut(),
+                        serde_json::json!({
+                            "content": content,
+                            "fm": frontmatter,
+                            "draft": page.draft,
+                            "url": page.url_path,
+                            "sections": page.sections,
+                        }),
+                    );
+                    let rendered_result = render.render_with_context(template, item_context)?;
 
-        fs::write(&page.output_path, &rendered_result)?;
+                    if let Some(parent) = page.output_path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    output::write_if_changed(&page.output_path, &rendered_result)?;
+                    Ok(RenderedPage {
+                        output_path: page.output_path.clone(),
+                        duration: start.elapsed(),
+                    })
+                },
+            )
+            .collect::<std::io::Result<Vec<_>>>()?;
+        rendered.extend(collection_rendered);
     }
 
-    Ok(())
+    Ok(rendered)
+}
+
+/// Renders the collection list pages of `pages` with the list template of
+/// their collection
+///
+/// Items are listed newest first according to their `date` frontmatter field,
+/// undated items coming last in discovery order. `site_pages` holds every
+/// page of the site, so that each list page knows the whole collection.
+pub fn render_collection_lists(
+    parsed_config: &config::ResolvedConfig,
+    pages: &[PageEntry],
+    site_pages: &SitePages,
+    render: &HandlebarsRenderer,
+) -> std::io::Result<Vec<RenderedPage>> {
+    let mut list_templates: HashMap<&str, String> = HashMap::new();
+    let mut items: HashMap<&str, Vec<&PageEntry>> = HashMap::new();
+    let mut trees: HashMap<&str, serde_json::Value> = HashMap::new();
+    let mut page_urls: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for page in pages {
+        let collection_name = match &page.page_type {
+            PageType::CollectionList { name, .. } => name,
+            _ => continue,
+        };
+
+        if !list_templates.contains_key(collection_name.as_str()) {
+            log::info!("Rendering list of collection {}", collection_name);
+
+            list_templates.insert(collection_name, fs::read_to_string(&page.source_path)?);
+
+            let mut collection_items = site_pages.collection(collection_name);
+            trees.insert(
+                collection_name,
+                context::collection_tree(collection_name, &collection_items),
+            );
+            sort_newest_first(&mut collection_items);
+            items.insert(collection_name, collection_items);
+
+            let mut list_pages: Vec<(usize, &str)> = site_pages
+                .collection_lists()
+                .into_iter()
+                .filter_map(|list_page| match &list_page.page_type {
+                    PageType::CollectionList { name, page } if name == collection_name => {
+                        Some((*page, list_page.url_path.as_str()))
+                    }
+                    _ => None,
+                })
+                .collect();
+            list_pages.sort();
+            page_urls.insert(
+                collection_name,
+                list_pages.into_iter().map(|(_, url)| url).collect(),
+            );
+        }
+    }
+
+    pages
+        .par_iter()
+        .filter_map(|page| match &page.page_type {
+            PageType::CollectionList { name, page: number } => Some((page, name, *number)),
+            _ => None,
+        })
+        .map(|(page, collection_name, number)| {
+            let start = Instant::now();
+
+            let pagination = context::pagination(
+                &items[collection_name.as_str()],
+                number,
+                parsed_config.collection(collection_name).page_size(),
+                &page_urls[collection_name.as_str()],
+            );
+            let rendered_result = render.render(
+                list_templates[collection_name.as_str()].clone(),
+                merge_contexts(
+                    parsed_config,
+                    serde_json::json!({
+                        "url": page.url_path,
+                        "collection": trees[collection_name.as_str()],
+                        "pagination": pagination,
+                    }),
+                ),
+            )?;
+
+            if let Some(parent) = page.output_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            output::write_if_changed(&page.output_path, &rendered_result)?;
+            Ok(RenderedPage {
+                output_path: page.output_path.clone(),
+                duration: start.elapsed(),
+            })
+        })
+        .collect()
 }
 
+/// Renders the taxonomy pages of `pages` with the index and term templates of
+/// their taxonomy
+///
+/// Terms are sorted by slug and their items listed newest first, like on
+/// collection list pages. `site_pages` holds every page of the site, so that
+/// terms list items of every collection they classify.
+pub fn render_taxonomies(
+    parsed_config: &config::ResolvedConfig,
+    pages: &[PageEntry],
+    site_pages: &SitePages,
+    render: &HandlebarsRenderer,
+) -> std::io::Result<Vec<RenderedPage>> {
+    let mut templates: HashMap<&Path, String> = HashMap::new();
+    let mut terms: HashMap<&str, Vec<serde_json::Value>> = HashMap::new();
+
+    for page in pages {
+        let taxonomy_name = match page.page_type.taxonomy_name() {
+            Some(name) => name,
+            None => continue,
+        };
+
+        if !templates.contains_key(page.source_path.as_path()) {
+            templates.insert(&page.source_path, fs::read_to_string(&page.source_path)?);
+        }
+        if !terms.contains_key(taxonomy_name) {
+            log::info!("Rendering taxonomy {}", taxonomy_name);
+
+            let taxonomy_config = &parsed_config.taxonomies[taxonomy_name];
+            let items = taxonomy_items(taxonomy_config, site_pages.all().iter());
+            let term_urls: HashMap<&str, &str> = site_pages
+                .taxonomy_pages()
+                .into_iter()
+                .filter_map(|term_page| match &term_page.page_type {
+                    PageType::TaxonomyTerm { taxonomy, term } if taxonomy == taxonomy_name => {
+                        Some((term.as_str(), term_page.url_path.as_str()))
+                    }
+                    _ => None,
+                })
+                .collect();
+            terms.insert(
+                taxonomy_name,
+                taxonomy::group_terms(&items, &taxonomy_config.field)
+                    .iter_mut()
+                    .map(|term| {
+                        sort_newest_first(&mut term.items);
+                        context::taxonomy_term(term, term_urls.get(term.slug.as_str()).copied())
+                    })
+                    .collect(),
+            );
+        }
+    }
+
+    pages
+        .par_iter()
+        .filter_map(|page| page.page_type.taxonomy_name().map(|name| (page, name)))
+        .map(|(page, taxonomy_name)| {
+            let start = Instant::now();
+
+            let taxonomy_terms = &terms[taxonomy_name];
+            let local_context = match &page.page_type {
+                PageType::TaxonomyTerm { term, .. } => serde_json::json!({
+                    "url": page.url_path,
+                    "taxonomy": taxonomy_name,
+                    "term": taxonomy_terms.iter().find(|t| t["slug"] == *term),
+                }),
+                _ => serde_json::json!({
+                    "url": page.url_path,
+                    "taxonomy": taxonomy_name,
+                    "terms": taxonomy_terms,
+                }),
+            };
+            let rendered_result = render.render(
+                templates[page.source_path.as_path()].clone(),
+                merge_contexts(parsed_config, local_context),
+            )?;
+
+            if let Some(parent) = page.output_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            output::write_if_changed(&page.output_path, &rendered_result)?;
+            Ok(RenderedPage {
+                output_path: page.output_path.clone(),
+                duration: start.elapsed(),
+            })
+        })
+        .collect()
+}
+
+/// Sorts `items` newest first according to their `date` frontmatter field,
+/// undated items coming last in their current order
+fn sort_newest_first(items: &mut [&PageEntry]) {
+    items.sort_by_cached_key(|item| {
+        let date = item
+            .frontmatter
+            .as_ref()
+            .and_then(|fm| date::frontmatter_date(fm, "date").ok().flatten());
+        std::cmp::Reverse(date)
+    });
+}
+
+/// Writes the sitemap if one is configured, returning its path
 pub fn write_sitemap(
     parsed_config: &config::ResolvedConfig,
     site_pages: &SitePages,
-) -> std::io::Result<()> {
+) -> std::io::Result<Option<PathBuf>> {
     let base_url = match &parsed_config.base_url {
         Some(url) => url,
         None => {
             log::debug!("No base_url configured, skipping sitemap generation");
-            return Ok(());
+            return Ok(None);
         }
     };
 
@@ -331,7 +1017,7 @@ pub fn write_sitemap(
 
     if !sitemap_config.enabled {
         log::debug!("Sitemap generation is disabled");
-        return Ok(());
+        return Ok(None);
     }
 
     let xml = sitemap::generate_sitemap(site_pages, base_url, &sitemap_config);
@@ -340,7 +1026,15 @@ pub fn write_sitemap(
         .join(&sitemap_config.filename);
 
     log::info!("Writing sitemap to {}", sitemap_path.display());
-    fs::write(&sitemap_path, xml)?;
+    output::write_if_changed(&sitemap_path, xml)?;
 
-    Ok(())
+    Ok(Some(sitemap_path))
+}
+
+/// Reads a directory and returns its entries sorted by file name, so that
+/// discovery does not depend on the file system's iteration order
+pub(crate) fn sorted_entries(directory: &Path) -> std::io::Result<Vec<fs::DirEntry>> {
+    let mut entries = fs::read_dir(directory)?.collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    Ok(entries)
 }
@@ -1,7 +1,11 @@
 use std::path::PathBuf;
 
-use balzac::cli;
+use chrono::{DateTime, Utc};
+
+use balzac::cli::{self, BuildOptions};
 use balzac::config::InitFeature;
+use balzac::date::parse_date;
+use balzac::starter::StarterTemplate;
 
 fn main() {
     colog::init();
@@ -16,6 +20,52 @@ fn main() {
                     clap::arg!(--root <PATH>)
                         .value_parser(clap::value_parser!(PathBuf))
                         .required(false),
+                )
+                .arg(
+                    clap::arg!(--env <ENV>)
+                        .help("Merge balzac.<ENV>.toml over balzac.toml, defaults to BALZAC_ENV")
+                        .required(false),
+                )
+                .arg(
+                    clap::arg!(--clean)
+                        .help("Ignore the build cache and rebuild every page")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::arg!(--force)
+                        .help("Clean the output directory even if it is outside the project root or overlaps a source directory")
+                        .requires("clean")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::arg!(--drafts)
+                        .help("Build collection items marked as drafts, and list them in the sitemap")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::arg!(--now <DATE>)
+                        .help("Publish collection items as if the build ran at DATE (YYYY-MM-DD or RFC 3339)")
+                        .value_parser(parse_now)
+                        .required(false),
+                )
+                .arg(
+                    clap::arg!(--watch)
+                        .help("Rebuild the project whenever a source file changes")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::arg!(--report <FORMAT>)
+                        .help("Write a build report, to balzac-report.json unless --report-file is given")
+                        .value_parser(["json"])
+                        .conflicts_with("watch")
+                        .required(false),
+                )
+                .arg(
+                    clap::arg!(--"report-file" <PATH>)
+                        .help("File the build report is written to")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .requires("report")
+                        .required(false),
                 ),
         )
         .subcommand(
@@ -30,6 +80,108 @@ fn main() {
                     clap::arg!(--sitemap)
                         .help("Include sitemap configuration")
                         .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::arg!(--vite)
+                        .help("Include vite bundler configuration and files")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::arg!(--hooks)
+                        .help("Include example hooks configuration")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::arg!(--template <TEMPLATE>)
+                        .help("Generate a starter site")
+                        .value_parser(StarterTemplate::NAMES)
+                        .required(false),
+                ),
+        )
+        .subcommand(
+            clap::command!("new")
+                .about("Create a new content file from an archetype")
+                .arg(clap::arg!(<COLLECTION> "Collection to add the content to"))
+                .arg(clap::arg!(<TITLE> "Title of the content"))
+                .arg(
+                    clap::arg!(--root <PATH>)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .required(false),
+                ),
+        )
+        .subcommand(
+            clap::command!("check")
+                .about("Validate project without writing any output")
+                .arg(
+                    clap::arg!(--root <PATH>)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .required(false),
+                )
+                .arg(
+                    clap::arg!(--env <ENV>)
+                        .help("Merge balzac.<ENV>.toml over balzac.toml, defaults to BALZAC_ENV")
+                        .required(false),
+                ),
+        )
+        .subcommand(
+            clap::command!("routes")
+                .about("List the URL of every page and where it is built from")
+                .arg(
+                    clap::arg!(--root <PATH>)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .required(false),
+                )
+                .arg(
+                    clap::arg!(--env <ENV>)
+                        .help("Merge balzac.<ENV>.toml over balzac.toml, defaults to BALZAC_ENV")
+                        .required(false),
+                )
+                .arg(
+                    clap::arg!(--drafts)
+                        .help("Include collection items marked as drafts")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::arg!(--format <FORMAT>)
+                        .help("Output format")
+                        .value_parser(["table", "json"])
+                        .default_value("table"),
+                ),
+        )
+        .subcommand(
+            clap::command!("serve")
+                .about("Build, serve and live reload project using balzac")
+                .arg(
+                    clap::arg!(--root <PATH>)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .required(false),
+                )
+                .arg(
+                    clap::arg!(--env <ENV>)
+                        .help("Merge balzac.<ENV>.toml over balzac.toml, defaults to BALZAC_ENV")
+                        .required(false),
+                )
+                .arg(
+                    clap::arg!(--drafts)
+                        .help("Build collection items marked as drafts, and list them in the sitemap")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::arg!(--now <DATE>)
+                        .help("Publish collection items as if the build ran at DATE (YYYY-MM-DD or RFC 3339)")
+                        .value_parser(parse_now)
+                        .required(false),
+                )
+                .arg(
+                    clap::arg!(--host <HOST>)
+                        .help("Address to listen on")
+                        .default_value("127.0.0.1"),
+                )
+                .arg(
+                    clap::arg!(--port <PORT>)
+                        .help("Port to listen on")
+                        .value_parser(clap::value_parser!(u16))
+                        .default_value("3000"),
                 ),
         );
 
@@ -42,11 +194,83 @@ fn main() {
             if sub_matches.get_flag("sitemap") {
                 features.push(InitFeature::Sitemap);
             }
-            cli::init(&path, &features);
+            if sub_matches.get_flag("vite") {
+                features.push(InitFeature::Vite);
+            }
+            if sub_matches.get_flag("hooks") {
+                features.push(InitFeature::Hooks);
+            }
+            let template = sub_matches
+                .get_one::<String>("template")
+                .and_then(|name| StarterTemplate::from_name(name));
+            cli::init(&path, &features, template);
         }
         Some(("build", sub_matches)) => {
             let path = get_path_arg(sub_matches, "root");
-            cli::build(&path);
+            let environment = get_environment_arg(sub_matches);
+            let options = BuildOptions {
+                environment: environment.as_deref(),
+                clean: sub_matches.get_flag("clean"),
+                force: sub_matches.get_flag("force"),
+                drafts: sub_matches.get_flag("drafts"),
+                now: sub_matches.get_one::<DateTime<Utc>>("now").copied(),
+            };
+            if sub_matches.get_flag("watch") {
+                cli::watch(&path, &options);
+            } else {
+                let report_path = sub_matches.contains_id("report").then(|| {
+                    sub_matches
+                        .get_one::<PathBuf>("report-file")
+                        .cloned()
+                        .unwrap_or_else(|| path.join("balzac-report.json"))
+                });
+                cli::build(&path, &options, report_path.as_deref());
+            }
+        }
+        Some(("new", sub_matches)) => {
+            let path = get_path_arg(sub_matches, "root");
+            let collection = sub_matches
+                .get_one::<String>("COLLECTION")
+                .expect("collection is required");
+            let title = sub_matches
+                .get_one::<String>("TITLE")
+                .expect("title is required");
+            cli::new(&path, collection, title);
+        }
+        Some(("check", sub_matches)) => {
+            let path = get_path_arg(sub_matches, "root");
+            let environment = get_environment_arg(sub_matches);
+            cli::check(&path, environment.as_deref());
+        }
+        Some(("routes", sub_matches)) => {
+            let path = get_path_arg(sub_matches, "root");
+            let environment = get_environment_arg(sub_matches);
+            let options = BuildOptions {
+                environment: environment.as_deref(),
+                drafts: sub_matches.get_flag("drafts"),
+                ..Default::default()
+            };
+            let json = sub_matches
+                .get_one::<String>("format")
+                .is_some_and(|format| format == "json");
+            cli::routes(&path, &options, json);
+        }
+        Some(("serve", sub_matches)) => {
+            let path = get_path_arg(sub_matches, "root");
+            let host = sub_matches
+                .get_one::<String>("host")
+                .expect("host has a default value");
+            let port = sub_matches
+                .get_one::<u16>("port")
+                .expect("port has a default value");
+            let environment = get_environment_arg(sub_matches);
+            let options = BuildOptions {
+                environment: environment.as_deref(),
+                drafts: sub_matches.get_flag("drafts"),
+                now: sub_matches.get_one::<DateTime<Utc>>("now").copied(),
+                ..Default::default()
+            };
+            cli::serve(&path, &options, &format!("{}:{}", host, port));
         }
         _ => unreachable!(),
     }
@@ -65,3 +289,17 @@ fn get_path_arg(matches: &clap::ArgMatches, name: &str) -> PathBuf {
         }
     }
 }
+
+/// Returns the environment selected with `--env`, falling back to the
+/// BALZAC_ENV environment variable
+fn get_environment_arg(matches: &clap::ArgMatches) -> Option<String> {
+    matches
+        .get_one::<String>("env")
+        .cloned()
+        .or_else(|| std::env::var("BALZAC_ENV").ok())
+        .filter(|environment| !environment.is_empty())
+}
+
+fn parse_now(value: &str) -> Result<DateTime<Utc>, String> {
+    parse_date(value).ok_or_else(|| "expected YYYY-MM-DD or an RFC 3339 date".to_string())
+}
@@ -0,0 +1,391 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ResolvedConfig;
+
+/// Name of the manifest file, stored in the output directory
+pub const MANIFEST_FILENAME: &str = ".balzac-manifest.json";
+
+/// Files of the output directory that were written by balzac
+///
+/// Only files listed in the manifest of the previous build are ever removed
+/// from the output directory, so that files written there by other tools
+/// (like vite) are left alone.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OutputManifest {
+    /// Paths relative to the output directory
+    pub files: BTreeSet<String>,
+}
+
+impl OutputManifest {
+    /// Loads the manifest of the previous build, if there is a usable one
+    pub fn load(configuration: &ResolvedConfig) -> Option<Self> {
+        let path = manifest_path(configuration);
+        let content = fs::read_to_string(&path).ok()?;
+        match serde_json::from_str::<Self>(&content) {
+            Ok(mut manifest) => {
+                // Keys are joined onto the output directory before deleting
+                // files, so anything that could point outside of it is dropped
+                manifest.files.retain(|key| {
+                    let is_safe = is_output_key(key);
+                    if !is_safe {
+                        log::warn!("Ignoring invalid output manifest entry {:?}", key);
+                    }
+                    is_safe
+                });
+                Some(manifest)
+            }
+            Err(e) => {
+                log::warn!(
+                    "Ignoring unreadable output manifest {}: {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, configuration: &ResolvedConfig) -> io::Result<()> {
+        let content = serde_json::to_string(self).map_err(io::Error::other)?;
+        write_if_changed(&manifest_path(configuration), content)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, configuration: &ResolvedConfig, output_path: &Path) {
+        match output_key(configuration, output_path) {
+            Some(key) => {
+                self.files.insert(key);
+            }
+            None => log::warn!(
+                "Not recording {}, it is outside of the output directory",
+                output_path.display()
+            ),
+        }
+    }
+
+    /// Files of this (previous) manifest that `current` no longer lists, as
+    /// absolute paths
+    pub fn stale_outputs(
+        &self,
+        configuration: &ResolvedConfig,
+        current: &OutputManifest,
+    ) -> Vec<PathBuf> {
+        self.files
+            .difference(&current.files)
+            .map(|key| configuration.output_directory.join(key))
+            .collect()
+    }
+}
+
+/// Writes `content` to `path` unless the file already holds exactly that
+/// content, so that unchanged outputs keep their modification time
+///
+/// Returns true if the file was written.
+pub fn write_if_changed(path: &Path, content: impl AsRef<[u8]>) -> io::Result<bool> {
+    let content = content.as_ref();
+    if let Ok(metadata) = fs::metadata(path)
+        && metadata.len() == content.len() as u64
+        && fs::read(path)? == content
+    {
+        return Ok(false);
+    }
+
+    fs::write(path, content)?;
+    Ok(true)
+}
+
+/// Copies `from` to `to` unless `to` already holds the same content
+///
+/// Returns true if the file was copied.
+pub fn copy_if_changed(from: &Path, to: &Path) -> io::Result<bool> {
+    if let (Ok(from_metadata), Ok(to_metadata)) = (fs::metadata(from), fs::metadata(to))
+        && from_metadata.len() == to_metadata.len()
+        && fs::read(from)? == fs::read(to)?
+    {
+        return Ok(false);
+    }
+
+    fs::copy(from, to)?;
+    Ok(true)
+}
+
+/// Deletes stale output files, along with any directory left empty
+///
+/// Only paths strictly inside the output directory, once symlinks and `..`
+/// are resolved, are ever deleted.
+pub fn remove_stale_outputs(configuration: &ResolvedConfig, paths: &[PathBuf]) -> io::Result<()> {
+    let output_directory = normalize(&configuration.output_directory);
+    let is_inside_output = |path: &Path| {
+        // The file itself is not resolved, so that a symlink gets removed
+        // rather than the file it points to
+        let resolved = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => normalize(parent).join(name),
+            _ => return false,
+        };
+        resolved != output_directory && resolved.starts_with(&output_directory)
+    };
+
+    for path in paths {
+        if !is_inside_output(path) {
+            log::warn!(
+                "Not removing {}, it is outside of the output directory",
+                path.display()
+            );
+            continue;
+        }
+        if fs::symlink_metadata(path).is_err() {
+            continue;
+        }
+
+        log::debug!("Removing stale output {}", path.display());
+        fs::remove_file(path)?;
+
+        let mut parent = path.parent();
+        while let Some(dir) = parent {
+            if !is_inside_output(dir) || fs::read_dir(dir)?.next().is_some() {
+                break;
+            }
+            fs::remove_dir(dir)?;
+            parent = dir.parent();
+        }
+    }
+    Ok(())
+}
+
+/// Makes sure the output directory can be deleted as a whole without
+/// destroying anything else
+///
+/// The output directory has to be strictly inside the project root, must not
+/// overlap any source directory and must not contain a balzac.toml file.
+pub fn ensure_safe_to_clean(configuration: &ResolvedConfig) -> io::Result<()> {
+    let root = normalize(&configuration.root_directory);
+    let output = normalize(&configuration.output_directory);
+    let refuse = |reason: String| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Refusing to clean output directory {}: {} (use --force to clean it anyway)",
+                configuration.output_directory.display(),
+                reason
+            ),
+        ))
+    };
+
+    if output == root || !output.starts_with(&root) {
+        return refuse(format!(
+            "it is not inside the project root {}",
+            configuration.root_directory.display()
+        ));
+    }
+
+    let sources = [
+        ("pages", &configuration.pages_directory),
+        ("layouts", &configuration.layouts_directory),
+        ("partials", &configuration.partials_directory),
+        ("assets", &configuration.assets_directory),
+        ("content", &configuration.content_directory),
+        ("archetypes", &configuration.archetypes_directory),
+    ];
+    let static_sources = configuration
+        .static_mounts
+        .iter()
+        .map(|mount| ("static", &mount.from));
+    for (name, directory) in sources.into_iter().chain(static_sources) {
+        let source = normalize(directory);
+        if output.starts_with(&source) || source.starts_with(&output) {
+            return refuse(format!(
+                "it overlaps the {} directory {}",
+                name,
+                directory.display()
+            ));
+        }
+    }
+
+    if output.join("balzac.toml").exists() {
+        return refuse("it contains a balzac.toml file".to_string());
+    }
+
+    Ok(())
+}
+
+/// Makes `path` absolute and resolves symlinks, `.` and `..`, even if the end
+/// of the path does not exist yet
+fn normalize(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut missing = Vec::new();
+
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            let mut normalized = canonical;
+            for component in missing.iter().rev() {
+                match component {
+                    Component::ParentDir => {
+                        normalized.pop();
+                    }
+                    Component::CurDir => {}
+                    component => normalized.push(component),
+                }
+            }
+            return normalized;
+        }
+
+        match (existing.parent(), existing.components().next_back()) {
+            (Some(parent), Some(component)) => {
+                missing.push(component);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+fn manifest_path(configuration: &ResolvedConfig) -> PathBuf {
+    configuration.output_directory.join(MANIFEST_FILENAME)
+}
+
+/// Path of an output relative to the output directory, none if it is not
+/// inside of it
+pub(crate) fn output_key(configuration: &ResolvedConfig, output_path: &Path) -> Option<String> {
+    let key = output_path
+        .strip_prefix(&configuration.output_directory)
+        .ok()?
+        .to_string_lossy()
+        .to_string();
+    is_output_key(&key).then_some(key)
+}
+
+/// Returns true if `key` is a relative path made of plain file and directory
+/// names only
+fn is_output_key(key: &str) -> bool {
+    !key.is_empty()
+        && Path::new(key)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_if_changed_keeps_mtime() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("index.html");
+
+        assert!(write_if_changed(&path, "hello").unwrap());
+        let old_mtime = SystemTime::now() - Duration::from_secs(3600);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old_mtime)
+            .unwrap();
+
+        assert!(!write_if_changed(&path, "hello").unwrap());
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), old_mtime);
+
+        assert!(write_if_changed(&path, "hellO").unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hellO");
+    }
+
+    #[test]
+    fn test_ensure_safe_to_clean() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let with_output = |output_directory: &str| {
+            let config = Config {
+                output_directory: output_directory.to_string(),
+                ..Default::default()
+            };
+            ensure_safe_to_clean(&config.resolve(root))
+        };
+
+        assert!(with_output("./dist").is_ok());
+        assert!(with_output("./build/site").is_ok());
+        assert!(with_output(".").is_err());
+        assert!(with_output("./dist/..").is_err());
+        assert!(with_output("..").is_err());
+        assert!(with_output("/").is_err());
+        assert!(with_output("./content").is_err());
+        assert!(with_output("./pages/out").is_err());
+
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("nested/balzac.toml"), "").unwrap();
+        assert!(with_output("./nested").is_err());
+
+        let error = with_output("./assets").unwrap_err();
+        assert!(error.to_string().contains("overlaps the assets directory"));
+    }
+
+    #[test]
+    fn test_stale_outputs() {
+        let temp_dir = TempDir::new().unwrap();
+        let configuration = Config::default().resolve(temp_dir.path());
+
+        let mut previous = OutputManifest::default();
+        for file in ["about.html", "old.html", "assets/old.css"] {
+            previous.record(&configuration, &configuration.output_directory.join(file));
+        }
+
+        let mut current = OutputManifest::default();
+        current.record(
+            &configuration,
+            &configuration.output_directory.join("about.html"),
+        );
+
+        assert_eq!(
+            previous.stale_outputs(&configuration, &current),
+            vec![
+                configuration.output_directory.join("assets/old.css"),
+                configuration.output_directory.join("old.html"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_manifest_entries_outside_output_are_ignored() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("site");
+        let configuration = Config::default().resolve(&root);
+        fs::create_dir_all(configuration.output_directory.join("blog")).unwrap();
+        fs::write(configuration.output_directory.join("blog/old.html"), "old").unwrap();
+        let victim = temp_dir.path().join("victim.txt");
+        fs::write(&victim, "victim").unwrap();
+
+        fs::write(
+            manifest_path(&configuration),
+            r#"{"files": ["../../victim.txt", "/etc/hosts", "blog/old.html", "./x", ""]}"#,
+        )
+        .unwrap();
+        let previous = OutputManifest::load(&configuration).unwrap();
+        assert_eq!(
+            previous.files.iter().collect::<Vec<_>>(),
+            vec!["blog/old.html"]
+        );
+
+        let stale = previous.stale_outputs(&configuration, &OutputManifest::default());
+        remove_stale_outputs(&configuration, &stale).unwrap();
+        assert!(!configuration.output_directory.join("blog").exists());
+        assert!(configuration.output_directory.exists());
+
+        // Even paths that were not read from a manifest are checked
+        remove_stale_outputs(
+            &configuration,
+            &[configuration.output_directory.join("../../victim.txt")],
+        )
+        .unwrap();
+        assert!(victim.exists());
+        assert_eq!(
+            output_key(&configuration, &temp_dir.path().join("victim.txt")),
+            None
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use std::path::{Path, PathBuf};
+
+use chrono::Datelike;
+use serde_json::Value;
+
+use crate::archetype::slugify;
+use crate::config::UrlStyle;
+use crate::date::frontmatter_day;
+
+/// What the placeholders of a permalink pattern are replaced with
+///
+/// - `{collection}`: name of the collection
+/// - `{slug}`: slug of the item, see [`item_slug`]
+/// - `{sections}`: sections of the item, like `guides/advanced`
+/// - `{year}`, `{month}` and `{day}`: day of the `date` frontmatter field, as
+///   written, see [`crate::date::parse_day`]
+/// - `{<field>}`: any other frontmatter field, slugified
+pub struct PermalinkFields<'a> {
+    pub collection: &'a str,
+    pub slug: &'a str,
+    pub sections: &'a [String],
+    pub frontmatter: &'a Value,
+}
+
+impl PermalinkFields<'_> {
+    fn get(&self, name: &str) -> Result<String, String> {
+        match name {
+            "collection" => Ok(self.collection.to_string()),
+            "slug" => Ok(self.slug.to_string()),
+            "sections" => Ok(self.sections.join("/")),
+            "year" | "month" | "day" => {
+                let date = frontmatter_day(self.frontmatter, "date")?
+                    .ok_or_else(|| format!("{{{}}} needs a date frontmatter field", name))?;
+                Ok(match name {
+                    "year" => format!("{:04}", date.year()),
+                    "month" => format!("{:02}", date.month()),
+                    _ => format!("{:02}", date.day()),
+                })
+            }
+            field => match self.frontmatter.get(field) {
+                Some(Value::String(value)) => Ok(slugify(value)),
+                Some(Value::Number(value)) => Ok(value.to_string()),
+                Some(Value::Bool(value)) => Ok(value.to_string()),
+                None | Some(Value::Null) => Err(format!("missing frontmatter field {}", field)),
+                Some(value) => Err(format!(
+                    "frontmatter field {} cannot be used in a permalink: {}",
+                    field, value
+                )),
+            },
+        }
+    }
+}
+
+/// Returns the `slug` frontmatter field, or `file_stem` if there is none
+pub fn item_slug(frontmatter: &Value, file_stem: &str) -> Result<String, String> {
+    match frontmatter.get("slug") {
+        None | Some(Value::Null) => Ok(file_stem.to_string()),
+        Some(Value::String(slug))
+            if !slug.is_empty() && !slug.contains('/') && slug != "." && slug != ".." =>
+        {
+            Ok(slug.clone())
+        }
+        Some(value) => Err(format!(
+            "invalid slug {}, expected a non-empty string without /",
+            value
+        )),
+    }
+}
+
+/// Returns the `url` frontmatter field, which replaces the URL of an item
+/// altogether, starting with a `/`
+pub fn item_url(frontmatter: &Value) -> Result<Option<String>, String> {
+    match frontmatter.get("url") {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(url)) if !url.split('/').any(|segment| segment == "..") => {
+            Ok(Some(format!("/{}", url.trim_start_matches('/'))))
+        }
+        Some(value) => Err(format!(
+            "invalid url {}, expected a path like /about/",
+            value
+        )),
+    }
+}
+
+/// Replaces the `{placeholders}` of `pattern` with the values of `fields`
+///
+/// Empty path segments, like the one `{sections}` leaves for an item at the
+/// top of its collection, are removed. Permalinks with `.` or `..` segments
+/// are rejected, since they could leave the output directory.
+pub fn expand(pattern: &str, fields: &PermalinkFields) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("unclosed placeholder in permalink {}", pattern))?;
+        expanded.push_str(&fields.get(&rest[start + 1..start + end])?);
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+
+    let trailing_slash = expanded.ends_with('/');
+    let segments: Vec<&str> = expanded
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if segments
+        .iter()
+        .any(|segment| *segment == "." || *segment == "..")
+    {
+        return Err(format!(
+            "invalid permalink {}, expected no . or .. segments",
+            expanded
+        ));
+    }
+    let mut permalink = format!("/{}", segments.join("/"));
+    if trailing_slash && !segments.is_empty() {
+        permalink.push('/');
+    }
+    Ok(permalink)
+}
+
+/// Returns the URL of an expanded permalink, along with its output path
+/// relative to the output directory
+///
+/// Permalinks ending with `/` are written to an `index.html` file and
+/// permalinks ending with `.html` to that file, others follow `url_style`.
+pub fn locate(permalink: &str, url_style: UrlStyle) -> (String, PathBuf) {
+    let path = permalink.trim_start_matches('/');
+    if permalink.ends_with('/') {
+        (permalink.to_string(), Path::new(path).join("index.html"))
+    } else if permalink.ends_with(".html") {
+        (permalink.to_string(), PathBuf::from(path))
+    } else {
+        url_style.locate(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let frontmatter = serde_json::json!({
+            "date": "2024-03-07",
+            "category": "Release Notes",
+            "volume": 3
+        });
+        let sections = vec!["guides".to_string()];
+        let fields = PermalinkFields {
+            collection: "blog",
+            slug: "hello-world",
+            sections: &sections,
+            frontmatter: &frontmatter,
+        };
+
+        assert_eq!(
+            expand("/blog/{year}/{month}/{day}/{slug}/", &fields).unwrap(),
+            "/blog/2024/03/07/hello-world/"
+        );
+        assert_eq!(
+            expand("/{category}/{volume}/{slug}.html", &fields).unwrap(),
+            "/release-notes/3/hello-world.html"
+        );
+        assert_eq!(
+            expand("/{collection}/{sections}/{slug}", &fields).unwrap(),
+            "/blog/guides/hello-world"
+        );
+
+        let top_level = PermalinkFields {
+            sections: &[],
+            ..fields
+        };
+        assert_eq!(
+            expand("/{collection}/{sections}/{slug}", &top_level).unwrap(),
+            "/blog/hello-world"
+        );
+
+        assert!(expand("/{author}/{slug}", &fields).is_err());
+        assert!(expand("/{slug", &fields).is_err());
+        assert!(expand("/../../{slug}", &fields).is_err());
+        assert!(expand("/{collection}/./{slug}", &fields).is_err());
+
+        let dots = PermalinkFields {
+            slug: "..",
+            ..fields
+        };
+        assert!(expand("/{collection}/{slug}/", &dots).is_err());
+    }
+
+    #[test]
+    fn test_expand_uses_date_as_written() {
+        let expand_date = |date: &str| {
+            let frontmatter = serde_json::json!({ "date": date });
+            let fields = PermalinkFields {
+                collection: "blog",
+                slug: "a",
+                sections: &[],
+                frontmatter: &frontmatter,
+            };
+            expand("/{year}/{month}/{day}/{slug}/", &fields).unwrap()
+        };
+
+        // Same results whatever the timezone of the machine running the build
+        assert_eq!(expand_date("2024-03-07T00:30:00+02:00"), "/2024/03/07/a/");
+        assert_eq!(expand_date("2024-03-06T23:30:00-05:00"), "/2024/03/06/a/");
+        assert_eq!(expand_date("2024-03-07T23:59:00Z"), "/2024/03/07/a/");
+        assert_eq!(expand_date("2024-03-07 23:59:00"), "/2024/03/07/a/");
+        assert_eq!(expand_date("2024-03-07"), "/2024/03/07/a/");
+    }
+
+    #[test]
+    fn test_item_slug_and_url() {
+        let frontmatter = serde_json::json!({"slug": "custom", "url": "about/"});
+        assert_eq!(item_slug(&frontmatter, "post").unwrap(), "custom");
+        assert_eq!(item_url(&frontmatter).unwrap(), Some("/about/".to_string()));
+
+        let frontmatter = serde_json::json!({});
+        assert_eq!(item_slug(&frontmatter, "post").unwrap(), "post");
+        assert_eq!(item_url(&frontmatter).unwrap(), None);
+
+        let frontmatter = serde_json::json!({"slug": "a/b", "url": "/../etc"});
+        assert!(item_slug(&frontmatter, "post").is_err());
+        assert!(item_url(&frontmatter).is_err());
+        assert_eq!(
+            item_url(&serde_json::json!({"url": "/"})).unwrap(),
+            Some("/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_locate() {
+        assert_eq!(
+            locate("/blog/2024/hello/", UrlStyle::Extensionless),
+            (
+                "/blog/2024/hello/".to_string(),
+                PathBuf::from("blog/2024/hello/index.html")
+            )
+        );
+        assert_eq!(
+            locate("/blog/hello.html", UrlStyle::Pretty),
+            (
+                "/blog/hello.html".to_string(),
+                PathBuf::from("blog/hello.html")
+            )
+        );
+        assert_eq!(
+            locate("/blog/hello", UrlStyle::Pretty),
+            (
+                "/blog/hello/".to_string(),
+                PathBuf::from("blog/hello/index.html")
+            )
+        );
+    }
+}
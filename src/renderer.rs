@@ -1,4 +1,6 @@
 use std::fs;
+use std::io;
+use std::path::Path;
 
 use handlebars::Handlebars;
 
@@ -9,8 +11,8 @@ use crate::{
 
 pub trait Renderer {
     fn new(configuration: &config::ResolvedConfig) -> Self;
-    fn init(&mut self, configuration: &config::ResolvedConfig);
-    fn render(&self, template: String, data: serde_json::Value) -> String;
+    fn init(&mut self, configuration: &config::ResolvedConfig) -> io::Result<()>;
+    fn render(&self, template: String, data: serde_json::Value) -> io::Result<String>;
 }
 
 pub struct HandlebarsRenderer<'a> {
@@ -18,83 +20,107 @@ pub struct HandlebarsRenderer<'a> {
 }
 
 impl<'a> HandlebarsRenderer<'a> {
-    fn register_partials(&mut self, configuration: &config::ResolvedConfig) {
-        let partial_dir_exists = fs::exists(&configuration.partials_directory)
-            .expect("Could not check if partial dir exists");
+    fn register_partials(&mut self, configuration: &config::ResolvedConfig) -> io::Result<()> {
+        let partial_dir_exists = fs::exists(&configuration.partials_directory)?;
         if partial_dir_exists {
-            for entry in fs::read_dir(&configuration.partials_directory)
-                .expect("Could not read partials directory")
-            {
-                let dir = entry.expect("Could not get directory handler");
+            for entry in fs::read_dir(&configuration.partials_directory)? {
+                let dir = entry?;
                 log::debug!("Parsing partial {}", dir.file_name().to_string_lossy());
-                let partial_path = dir.path();
-                let partial_content =
-                    fs::read_to_string(&partial_path).expect("Cannot read partial file content");
-                let partial_name = &partial_path.file_stem().expect("Could not get file stem");
-                self.registry
-                    .register_partial(&partial_name.to_string_lossy(), partial_content)
-                    .expect("Cannot register partial");
+                self.register_template_file(&dir.path(), "partial")?;
             }
         } else {
             log::debug!("Could not find partial directory, skipping register step");
         }
+        Ok(())
     }
 
-    fn register_layouts(&mut self, configuration: &config::ResolvedConfig) {
-        let layouts_dir_exists = fs::exists(&configuration.layouts_directory)
-            .expect("Could not check if partial layouts dir exists");
+    fn register_layouts(&mut self, configuration: &config::ResolvedConfig) -> io::Result<()> {
+        let layouts_dir_exists = fs::exists(&configuration.layouts_directory)?;
 
         if layouts_dir_exists {
-            for entry in fs::read_dir(&configuration.layouts_directory)
-                .expect("Could not read layouts directory")
-            {
-                let dir = entry.expect("Could not get directory handler");
-                log::debug!("Parsing partial {}", dir.file_name().to_string_lossy());
-                let partial_path = dir.path();
-                let partial_content =
-                    fs::read_to_string(&partial_path).expect("Cannot read layout file content");
-                let partial_name = &partial_path.file_stem().expect("Could not get file stem");
-                self.registry
-                    .register_partial(&partial_name.to_string_lossy(), partial_content)
-                    .expect("Cannot register layout");
+            for entry in fs::read_dir(&configuration.layouts_directory)? {
+                let dir = entry?;
+                log::debug!("Parsing layout {}", dir.file_name().to_string_lossy());
+                self.register_template_file(&dir.path(), "layout")?;
             }
         } else {
             log::debug!("Could not find layouts directory, skipping register step");
         }
+        Ok(())
+    }
+
+    /// Registers a partial or layout file under its file stem
+    fn register_template_file(&mut self, path: &Path, kind: &str) -> io::Result<()> {
+        let content = fs::read_to_string(path)?;
+        let name = path
+            .file_stem()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Could not get file stem of {}", path.display()),
+                )
+            })?
+            .to_string_lossy();
+        self.registry.register_partial(&name, content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Cannot register {} {}: {}", kind, path.display(), e),
+            )
+        })
+    }
+
+    /// Renders `template` with an already built context, so that a large
+    /// context shared by many pages is not copied for each of them
+    pub fn render_with_context(
+        &self,
+        template: &str,
+        context: &handlebars::Context,
+    ) -> io::Result<String> {
+        self.registry
+            .render_template_with_context(template, context)
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Could not render template: {}", e),
+                )
+            })
     }
 
-    pub fn register_helpers(&mut self, configuration: &config::ResolvedConfig) {
+    pub fn register_helpers(&mut self, configuration: &config::ResolvedConfig) -> io::Result<()> {
         if let Some(bundler) = &configuration.bundler
             && let Some(vite) = &bundler.vite
             && vite.enabled
         {
             let manifest_path = std::path::PathBuf::from(&vite.manifest_path);
             let manifest = parse_manifest(manifest_path, &configuration.root_directory)
-                .unwrap_or_else(|e| {
-                    log::error!("{}", e);
-                    std::process::exit(1);
-                });
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
             let helper = vite_url { manifest };
             self.registry.register_helper("vite_url", Box::new(helper));
         }
+        Ok(())
     }
 }
 
 impl<'a> Renderer for HandlebarsRenderer<'a> {
-    fn init(&mut self, configuration: &config::ResolvedConfig) {
-        self.register_partials(configuration);
-        self.register_layouts(configuration);
-        self.register_helpers(configuration);
+    fn init(&mut self, configuration: &config::ResolvedConfig) -> io::Result<()> {
+        self.register_partials(configuration)?;
+        self.register_layouts(configuration)?;
+        self.register_helpers(configuration)
     }
     fn new(_configuration: &config::ResolvedConfig) -> HandlebarsRenderer<'a> {
         let reg = Handlebars::new();
 
         HandlebarsRenderer { registry: reg }
     }
-    fn render(&self, template: String, data: serde_json::Value) -> String {
+    fn render(&self, template: String, data: serde_json::Value) -> io::Result<String> {
         self.registry
             .render_template(&template, &data)
-            .expect("Could not render template")
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Could not render template: {}", e),
+                )
+            })
     }
 }
 
@@ -113,7 +139,7 @@ mod tests {
         let template = "Hello, World!".to_string();
         let data = serde_json::json!({});
 
-        let result = renderer.render(template, data);
+        let result = renderer.render(template, data).unwrap();
         assert_eq!(result, "Hello, World!");
     }
 
@@ -125,7 +151,7 @@ mod tests {
             "name": "Alice"
         });
 
-        let result = renderer.render(template, data);
+        let result = renderer.render(template, data).unwrap();
         assert_eq!(result, "Hello, Alice!");
     }
 
@@ -139,7 +165,7 @@ mod tests {
             "site": "Balzac"
         });
 
-        let result = renderer.render(template, data);
+        let result = renderer.render(template, data).unwrap();
         assert_eq!(result, "Hello Bob, welcome to Balzac!");
     }
 
@@ -154,7 +180,7 @@ mod tests {
             }
         });
 
-        let result = renderer.render(template, data);
+        let result = renderer.render(template, data).unwrap();
         assert_eq!(result, "Author: Jane Doe (jane@example.com)");
     }
 
@@ -166,7 +192,7 @@ mod tests {
             "is_active": true
         });
 
-        let result = renderer.render(template, data);
+        let result = renderer.render(template, data).unwrap();
         assert_eq!(result, "Active");
     }
 
@@ -178,7 +204,7 @@ mod tests {
             "items": ["Rust", "Handlebars", "SSG"]
         });
 
-        let result = renderer.render(template, data);
+        let result = renderer.render(template, data).unwrap();
         assert_eq!(result, "- Rust\n- Handlebars\n- SSG\n");
     }
 
@@ -190,7 +216,16 @@ mod tests {
             "unused": "data"
         });
 
-        let result = renderer.render(template, data);
+        let result = renderer.render(template, data).unwrap();
         assert_eq!(result, "");
     }
+
+    #[test]
+    fn test_render_invalid_template_returns_error() {
+        let renderer = create_renderer();
+        let template = "{{#if open}}never closed".to_string();
+
+        let result = renderer.render(template, serde_json::json!({}));
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+use crate::build::BuildReport;
+use crate::sitemap::PageEntry;
+
+/// Time spent in one phase of a build
+#[derive(Debug, Clone)]
+pub struct PhaseTiming {
+    pub name: &'static str,
+    pub duration: Duration,
+}
+
+/// A page written during a build
+#[derive(Debug, Clone)]
+pub struct RenderedPage {
+    pub output_path: PathBuf,
+    pub duration: Duration,
+}
+
+/// A source that was not turned into output
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedItem {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Things noticed during a build that did not stop it
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    pub warnings: Vec<String>,
+    pub skipped: Vec<SkippedItem>,
+    /// Number of pages that were up to date and not rendered again, which
+    /// are not listed in `skipped`
+    pub unchanged: usize,
+}
+
+impl Diagnostics {
+    /// Records `path` as skipped
+    pub fn skip(&mut self, path: &Path, reason: impl Into<String>) {
+        let reason = reason.into();
+        log::debug!("Skipping {}: {}", path.display(), reason);
+        self.skipped.push(SkippedItem {
+            path: path.to_path_buf(),
+            reason,
+        });
+    }
+
+    /// Records `path` as skipped because of a problem the user should fix
+    pub fn warn_skip(&mut self, path: &Path, reason: impl Into<String>) {
+        let reason = reason.into();
+        log::warn!("{}, skipping {}", reason, path.display());
+        self.warnings
+            .push(format!("{}: {}", path.display(), reason));
+        self.skipped.push(SkippedItem {
+            path: path.to_path_buf(),
+            reason,
+        });
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn page_json(page: &PageEntry, render_times: &HashMap<&Path, Duration>) -> Value {
+    let render_time_ms = render_times
+        .get(page.output_path.as_path())
+        .map(|duration| millis(*duration));
+    let bytes = fs::metadata(&page.output_path)
+        .ok()
+        .map(|metadata| metadata.len());
+
+    json!({
+        "url": page.url_path,
+        "source": page.source_path,
+        "output": page.output_path,
+        "type": page.page_type.kind(),
+        "collection": page.page_type.collection_name(),
+        "taxonomy": page.page_type.taxonomy_name(),
+        "bytes": bytes,
+        "rendered": render_time_ms.is_some(),
+        "render_time_ms": render_time_ms,
+    })
+}
+
+/// Serializes `report` into the JSON document written by `balzac build
+/// --report json`
+///
+/// Durations are in milliseconds. Page sizes are read from the output
+/// directory, so they are also given for pages that were up to date.
+pub fn to_json(report: &BuildReport) -> Value {
+    let render_times: HashMap<&Path, Duration> = report
+        .rendered
+        .iter()
+        .map(|rendered| (rendered.output_path.as_path(), rendered.duration))
+        .collect();
+    let pages: Vec<Value> = report
+        .site_pages
+        .all()
+        .iter()
+        .map(|page| page_json(page, &render_times))
+        .collect();
+    let phases: Vec<Value> = report
+        .phases
+        .iter()
+        .map(|phase| json!({"name": phase.name, "duration_ms": millis(phase.duration)}))
+        .collect();
+    let hooks: Vec<Value> = report
+        .hooks
+        .iter()
+        .map(|hook| {
+            json!({
+                "phase": hook.phase.name(),
+                "duration_ms": millis(hook.duration),
+            })
+        })
+        .collect();
+    let skipped: Vec<Value> = report
+        .diagnostics
+        .skipped
+        .iter()
+        .map(|item| json!({"path": item.path, "reason": item.reason}))
+        .collect();
+
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "duration_ms": millis(report.duration),
+        "total_pages": report.site_pages.all().len(),
+        "rendered_pages": report.rendered_pages,
+        "unchanged_pages": report.diagnostics.unchanged,
+        "phases": phases,
+        "hooks": hooks,
+        "pages": pages,
+        "assets": report.assets,
+        "removed_outputs": report.removed_outputs,
+        "warnings": report.diagnostics.warnings,
+        "skipped": skipped,
+    })
+}
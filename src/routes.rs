@@ -0,0 +1,335 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use serde_json::{Value, json};
+
+use crate::assets::CopiedFile;
+use crate::config::ResolvedConfig;
+use crate::report::Diagnostics;
+use crate::sitemap::{PageEntry, SitePages};
+use crate::{
+    discover_collections, discover_static_pages, discover_taxonomies, discover_valid_collections,
+    existing_static_files, static_files,
+};
+
+/// Kind of clash between two or more pages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictKind {
+    /// The pages are served at the same URL
+    DuplicateUrl,
+    /// The pages are written to the same output file
+    OutputCollision,
+}
+
+impl ConflictKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConflictKind::DuplicateUrl => "duplicate_url",
+            ConflictKind::OutputCollision => "output_collision",
+        }
+    }
+}
+
+/// Pages, or pages and copied files, that share a URL or an output file, only
+/// one of them ends up in the built site
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    /// The shared URL or output path
+    pub target: String,
+    /// Source files of the conflicting pages, in discovery order, followed by
+    /// the conflicting copied files
+    pub sources: Vec<PathBuf>,
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match self.kind {
+            ConflictKind::DuplicateUrl => "URL",
+            ConflictKind::OutputCollision => "output",
+        };
+        let sources: Vec<String> = self
+            .sources
+            .iter()
+            .map(|source| source.display().to_string())
+            .collect();
+        write!(
+            f,
+            "{} {} is produced by {}",
+            what,
+            self.target,
+            sources.join(", ")
+        )
+    }
+}
+
+/// Every page of the site, as a build would discover them
+#[derive(Debug, Clone)]
+pub struct Routes {
+    pub site_pages: SitePages,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Routes {
+    /// Runs page discovery without rendering anything
+    pub fn discover(configuration: &ResolvedConfig) -> std::io::Result<Self> {
+        let collection_pages = discover_collections(configuration)?;
+        Self::from_pages(
+            configuration,
+            collection_pages,
+            &static_files(configuration)?,
+        )
+    }
+
+    /// Same as [`Routes::discover`], leaving out the collection items and
+    /// static directories that cannot be discovered instead of failing, so
+    /// that the other pages can still be checked for conflicts
+    pub fn discover_valid(configuration: &ResolvedConfig) -> std::io::Result<Self> {
+        let collection_pages =
+            discover_valid_collections(configuration, &mut Diagnostics::default())?;
+        Self::from_pages(
+            configuration,
+            collection_pages,
+            &existing_static_files(configuration)?,
+        )
+    }
+
+    fn from_pages(
+        configuration: &ResolvedConfig,
+        collection_pages: Vec<PageEntry>,
+        files: &[CopiedFile],
+    ) -> std::io::Result<Self> {
+        let mut site_pages = SitePages::new();
+        let taxonomy_pages = discover_taxonomies(configuration, &collection_pages)?;
+        site_pages.add_pages(discover_static_pages(configuration)?);
+        site_pages.add_pages(collection_pages);
+        site_pages.add_pages(taxonomy_pages);
+        let conflicts = find_conflicts(site_pages.all(), files);
+
+        Ok(Self {
+            site_pages,
+            conflicts,
+        })
+    }
+
+    /// Formats the routes as a plain text table, with paths relative to the
+    /// project root
+    pub fn to_table(&self, configuration: &ResolvedConfig) -> String {
+        let root = &configuration.root_directory;
+        let mut rows = vec![[
+            "URL".to_string(),
+            "SOURCE".to_string(),
+            "OUTPUT".to_string(),
+            "TYPE".to_string(),
+            "COLLECTION".to_string(),
+            "SITEMAP".to_string(),
+        ]];
+        for page in self.site_pages.all() {
+            rows.push([
+                page.url_path.clone(),
+                relative(&page.source_path, root),
+                relative(&page.output_path, root),
+                page.page_type.kind().to_string(),
+                page.page_type.collection_name().unwrap_or("-").to_string(),
+                if in_sitemap(page) { "yes" } else { "no" }.to_string(),
+            ]);
+        }
+
+        let mut widths = [0; 6];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let mut table = String::new();
+        for row in &rows {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect();
+            table.push_str(cells.join("  ").trim_end());
+            table.push('\n');
+        }
+
+        table
+    }
+
+    /// Serializes the routes into the JSON document printed by `balzac routes
+    /// --format json`
+    pub fn to_json(&self) -> Value {
+        let pages: Vec<Value> = self
+            .site_pages
+            .all()
+            .iter()
+            .map(|page| {
+                json!({
+                    "url": page.url_path,
+                    "source": page.source_path,
+                    "output": page.output_path,
+                    "type": page.page_type.kind(),
+                    "collection": page.page_type.collection_name(),
+                    "taxonomy": page.page_type.taxonomy_name(),
+                    "sections": page.sections,
+                    "sitemap": in_sitemap(page),
+                })
+            })
+            .collect();
+        let conflicts: Vec<Value> = self
+            .conflicts
+            .iter()
+            .map(|conflict| {
+                json!({
+                    "kind": conflict.kind.name(),
+                    "target": conflict.target,
+                    "sources": conflict.sources,
+                })
+            })
+            .collect();
+
+        json!({
+            "pages": pages,
+            "conflicts": conflicts,
+        })
+    }
+}
+
+/// Finds the pages that share a URL, and the pages and copied `files` that
+/// share an output file
+pub fn find_conflicts(pages: &[PageEntry], files: &[CopiedFile]) -> Vec<Conflict> {
+    let mut urls: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    let mut outputs: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for page in pages {
+        urls.entry(page.url_path.clone())
+            .or_default()
+            .push(page.source_path.clone());
+        outputs
+            .entry(output_key(&page.output_path))
+            .or_default()
+            .push(page.source_path.clone());
+    }
+    for file in files {
+        outputs
+            .entry(output_key(&file.destination))
+            .or_default()
+            .push(file.source.clone());
+    }
+
+    let duplicates = |kind: ConflictKind, groups: BTreeMap<String, Vec<PathBuf>>| {
+        groups
+            .into_iter()
+            .filter(|(_, sources)| sources.len() > 1)
+            .map(move |(target, sources)| Conflict {
+                kind,
+                target,
+                sources,
+            })
+    };
+
+    duplicates(ConflictKind::DuplicateUrl, urls)
+        .chain(duplicates(ConflictKind::OutputCollision, outputs))
+        .collect()
+}
+
+/// Output paths of mount points like `./public` contain `.` components that
+/// the output paths of pages lack
+fn output_key(path: &Path) -> String {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect::<PathBuf>()
+        .display()
+        .to_string()
+}
+
+/// Whether the page is listed in the sitemap, provided one is generated, see
+/// [`SitePages::sitemap_pages`]
+fn in_sitemap(page: &PageEntry) -> bool {
+    !page.sitemap_meta.exclude
+}
+
+fn relative(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    use crate::config::Config;
+
+    #[test]
+    fn test_routes_flag_conflicts() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("pages/blog")).unwrap();
+        fs::write(root.join("pages/index.hbs"), "home").unwrap();
+        fs::write(root.join("pages/about.hbs"), "about").unwrap();
+        fs::write(root.join("pages/about.html"), "about").unwrap();
+        fs::write(root.join("pages/blog/details.hbs"), "{{title}}").unwrap();
+        fs::create_dir_all(root.join("content/blog")).unwrap();
+        fs::write(
+            root.join("content/blog/hidden.md"),
+            "---\ntitle: Hidden\nsitemap_exclude: true\n---\n\nHidden",
+        )
+        .unwrap();
+
+        let configuration = Config::default().resolve(root);
+        let routes = Routes::discover(&configuration).unwrap();
+
+        let urls: Vec<&str> = routes
+            .site_pages
+            .all()
+            .iter()
+            .map(|page| page.url_path.as_str())
+            .collect();
+        assert_eq!(urls, vec!["/about", "/about", "/", "/blog/hidden"]);
+
+        let kinds: Vec<ConflictKind> = routes.conflicts.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![ConflictKind::DuplicateUrl, ConflictKind::OutputCollision]
+        );
+        assert_eq!(routes.conflicts[0].target, "/about");
+        assert_eq!(routes.conflicts[0].sources.len(), 2);
+
+        let json = routes.to_json();
+        assert_eq!(json["pages"][3]["collection"], "blog");
+        assert_eq!(json["pages"][3]["sitemap"], false);
+        assert_eq!(json["conflicts"][1]["kind"], "output_collision");
+
+        let table = routes.to_table(&configuration);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("URL"));
+        assert!(lines[4].contains("content/blog/hidden.md"));
+        assert!(lines[4].ends_with("no"));
+        assert_eq!(lines.len(), 5);
+    }
+
+    #[test]
+    fn test_routes_list_drafts_in_sitemap_when_built() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("pages/blog")).unwrap();
+        fs::write(root.join("pages/blog/details.hbs"), "{{title}}").unwrap();
+        fs::create_dir_all(root.join("content/blog")).unwrap();
+        fs::write(
+            root.join("content/blog/wip.md"),
+            "---\ntitle: WIP\ndraft: true\n---\n\nWIP",
+        )
+        .unwrap();
+
+        let configuration = Config {
+            drafts: true,
+            ..Default::default()
+        }
+        .resolve(root);
+        let routes = Routes::discover(&configuration).unwrap();
+        assert_eq!(routes.to_json()["pages"][0]["sitemap"], true);
+    }
+}
@@ -0,0 +1,304 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use tiny_http::{Header, Request, Response, Server};
+
+/// URL the injected script listens on for reload events
+const RELOAD_ENDPOINT: &str = "/__balzac/reload";
+
+const RELOAD_SCRIPT: &str =
+    r#"<script>new EventSource("/__balzac/reload").onmessage = () => location.reload();</script>"#;
+
+/// Notifies connected browser tabs that the site has been rebuilt
+#[derive(Clone, Default)]
+pub struct LiveReload {
+    clients: Arc<Mutex<Vec<Sender<()>>>>,
+}
+
+impl LiveReload {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every open tab to reload, dropping tabs that have disconnected
+    pub fn reload(&self) {
+        let mut clients = self.clients.lock().expect("Live reload lock poisoned");
+        clients.retain(|client| client.send(()).is_ok());
+        log::debug!("Sent reload to {} client(s)", clients.len());
+    }
+
+    fn subscribe(&self) -> Receiver<()> {
+        let (tx, rx) = mpsc::channel();
+        self.clients
+            .lock()
+            .expect("Live reload lock poisoned")
+            .push(tx);
+        rx
+    }
+}
+
+/// Starts serving `root` on `address` in a background thread
+///
+/// HTML responses get a small script injected that reloads the page whenever
+/// `live_reload` is triggered.
+pub fn start(root: PathBuf, address: &str, live_reload: LiveReload) -> Result<(), String> {
+    let server = Server::http(address)
+        .map_err(|e| format!("Could not start server on {}: {}", address, e))?;
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let root = root.clone();
+            let live_reload = live_reload.clone();
+            thread::spawn(move || handle_request(request, &root, &live_reload));
+        }
+    });
+
+    Ok(())
+}
+
+fn handle_request(request: Request, root: &Path, live_reload: &LiveReload) {
+    let url = request.url().to_string();
+    let url_path = url.split(['?', '#']).next().unwrap_or("/");
+
+    if url_path == RELOAD_ENDPOINT {
+        stream_reload_events(request, live_reload);
+        return;
+    }
+
+    let result = match resolve_path(root, url_path) {
+        Some(file_path) => match fs::read(&file_path) {
+            Ok(body) => {
+                log::debug!("GET {} -> {}", url_path, file_path.display());
+                let content_type = content_type(&file_path);
+                let body = if content_type.starts_with("text/html") {
+                    inject_reload_script(&String::from_utf8_lossy(&body)).into_bytes()
+                } else {
+                    body
+                };
+                request.respond(Response::from_data(body).with_header(header(content_type)))
+            }
+            Err(e) => {
+                log::error!("Could not read {}: {}", file_path.display(), e);
+                request
+                    .respond(Response::from_string("Internal Server Error").with_status_code(500))
+            }
+        },
+        None => {
+            log::warn!("GET {} -> 404", url_path);
+            request.respond(
+                Response::from_string("Not Found")
+                    .with_status_code(404)
+                    .with_header(header("text/plain; charset=utf-8")),
+            )
+        }
+    };
+
+    if let Err(e) = result {
+        log::debug!("Could not send response for {}: {}", url_path, e);
+    }
+}
+
+/// Keeps the connection open as a server-sent event stream and writes an
+/// event every time a reload is requested
+fn stream_reload_events(request: Request, live_reload: &LiveReload) {
+    let events = live_reload.subscribe();
+    let mut writer = request.into_writer();
+
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n";
+    if writer
+        .write_all(head.as_bytes())
+        .and_then(|_| writer.flush())
+        .is_err()
+    {
+        return;
+    }
+
+    while events.recv().is_ok() {
+        if writer
+            .write_all(b"data: reload\n\n")
+            .and_then(|_| writer.flush())
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+/// Maps a request path to a file inside `root`
+///
+/// `/` and paths ending in `/` map to `index.html`, and extensionless paths
+/// such as `/about` fall back to `about.html` so that page URLs work as-is.
+/// The path is percent-decoded first, so `/my%20file.pdf` maps to
+/// `my file.pdf`.
+pub fn resolve_path(root: &Path, url_path: &str) -> Option<PathBuf> {
+    let url_path = percent_decode(url_path)?;
+    let relative = PathBuf::from(url_path.trim_start_matches('/'));
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let candidate = root.join(&relative);
+    if candidate.is_file() {
+        return Some(candidate);
+    }
+
+    let index = candidate.join("index.html");
+    if index.is_file() {
+        return Some(index);
+    }
+
+    let html = root.join(format!("{}.html", relative.display()));
+    if !url_path.ends_with('/') && html.is_file() {
+        return Some(html);
+    }
+
+    None
+}
+
+/// Decodes the `%XX` escapes of a request path, returning None for invalid
+/// escapes or paths that are not UTF-8
+fn percent_decode(path: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail
+                .get(..2)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+            bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Adds the live reload script to an HTML document, right before `</body>`
+/// when present and at the end of the document otherwise
+pub fn inject_reload_script(html: &str) -> String {
+    match html.rfind("</body>") {
+        Some(pos) => format!("{}{}{}", &html[..pos], RELOAD_SCRIPT, &html[pos..]),
+        None => format!("{}{}", html, RELOAD_SCRIPT),
+    }
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("html") | Some("htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") | Some("mjs") => "text/javascript; charset=utf-8",
+        Some("json") | Some("map") => "application/json",
+        Some("xml") => "application/xml",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("avif") => "image/avif",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        Some("pdf") => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+fn header(content_type: &str) -> Header {
+    Header::from_bytes("Content-Type", content_type).expect("Invalid content type header")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_output() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("index.html"), "home").unwrap();
+        fs::write(temp_dir.path().join("about.html"), "about").unwrap();
+        fs::create_dir(temp_dir.path().join("posts")).unwrap();
+        fs::write(temp_dir.path().join("posts").join("hello.html"), "hello").unwrap();
+        fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        fs::write(temp_dir.path().join("docs").join("index.html"), "docs").unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_resolve_index() {
+        let output = setup_output();
+        let root = output.path();
+        assert_eq!(resolve_path(root, "/"), Some(root.join("index.html")));
+        assert_eq!(
+            resolve_path(root, "/docs/"),
+            Some(root.join("docs").join("index.html"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_extensionless_urls() {
+        let output = setup_output();
+        let root = output.path();
+        assert_eq!(resolve_path(root, "/about"), Some(root.join("about.html")));
+        assert_eq!(
+            resolve_path(root, "/posts/hello"),
+            Some(root.join("posts").join("hello.html"))
+        );
+        assert_eq!(
+            resolve_path(root, "/about.html"),
+            Some(root.join("about.html"))
+        );
+        assert_eq!(resolve_path(root, "/missing"), None);
+    }
+
+    #[test]
+    fn test_resolve_rejects_traversal() {
+        let output = setup_output();
+        assert_eq!(resolve_path(output.path(), "/../secret"), None);
+        assert_eq!(resolve_path(output.path(), "/posts/../../secret"), None);
+        assert_eq!(resolve_path(output.path(), "/%2e%2e/secret"), None);
+        assert_eq!(resolve_path(output.path(), "/posts/..%2F..%2Fsecret"), None);
+    }
+
+    #[test]
+    fn test_resolve_percent_encoded_paths() {
+        let output = setup_output();
+        let root = output.path();
+        fs::write(root.join("my file.pdf"), "pdf").unwrap();
+        fs::write(root.join("café.html"), "café").unwrap();
+
+        assert_eq!(
+            resolve_path(root, "/my%20file.pdf"),
+            Some(root.join("my file.pdf"))
+        );
+        assert_eq!(
+            resolve_path(root, "/caf%C3%A9"),
+            Some(root.join("café.html"))
+        );
+        assert_eq!(resolve_path(root, "/my%2"), None);
+        assert_eq!(resolve_path(root, "/%ZZ"), None);
+        assert_eq!(resolve_path(root, "/%FF"), None);
+    }
+
+    #[test]
+    fn test_inject_reload_script() {
+        let html = "<html><body><p>hi</p></body></html>";
+        let injected = inject_reload_script(html);
+        assert!(injected.contains(RELOAD_ENDPOINT));
+        assert!(injected.ends_with("</script></body></html>"));
+
+        let fragment = inject_reload_script("<p>hi</p>");
+        assert!(fragment.starts_with("<p>hi</p><script>"));
+    }
+}
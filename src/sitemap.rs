@@ -1,12 +1,63 @@
+use chrono::{DateTime, Utc};
 use serde_json::Value;
 use std::path::PathBuf;
 
 use crate::config::SitemapConfig;
+use crate::date::frontmatter_date;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PageType {
     Static,
-    Collection { name: String },
+    Collection {
+        name: String,
+    },
+    /// Page `page` (starting at 1) of the paginated list of a collection
+    CollectionList {
+        name: String,
+        page: usize,
+    },
+    /// Index page of a taxonomy, listing its terms
+    TaxonomyList {
+        taxonomy: String,
+    },
+    /// Page of a single term of a taxonomy, identified by its slug
+    TaxonomyTerm {
+        taxonomy: String,
+        term: String,
+    },
+}
+
+impl PageType {
+    /// Name of the page type, as shown in reports
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PageType::Static => "static",
+            PageType::Collection { .. } => "collection",
+            PageType::CollectionList { .. } => "collection_list",
+            PageType::TaxonomyList { .. } => "taxonomy_list",
+            PageType::TaxonomyTerm { .. } => "taxonomy_term",
+        }
+    }
+
+    /// Name of the collection the page belongs to
+    pub fn collection_name(&self) -> Option<&str> {
+        match self {
+            PageType::Collection { name } | PageType::CollectionList { name, .. } => Some(name),
+            PageType::Static | PageType::TaxonomyList { .. } | PageType::TaxonomyTerm { .. } => {
+                None
+            }
+        }
+    }
+
+    /// Name of the taxonomy the page belongs to
+    pub fn taxonomy_name(&self) -> Option<&str> {
+        match self {
+            PageType::TaxonomyList { taxonomy } | PageType::TaxonomyTerm { taxonomy, .. } => {
+                Some(taxonomy)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -15,6 +66,10 @@ pub struct SitemapMeta {
     pub priority: Option<f32>,
     pub changefreq: Option<String>,
     pub exclude: bool,
+    /// The page is left out of builds made before this date
+    pub publish_date: Option<DateTime<Utc>>,
+    /// The page is left out of builds made after this date
+    pub expiry_date: Option<DateTime<Utc>>,
 }
 
 impl SitemapMeta {
@@ -37,10 +92,19 @@ impl SitemapMeta {
             if let Some(Value::Bool(exclude)) = obj.get("sitemap_exclude") {
                 meta.exclude = *exclude;
             }
+            meta.publish_date = frontmatter_date(fm, "publish_date").ok().flatten();
+            meta.expiry_date = frontmatter_date(fm, "expiry_date").ok().flatten();
         }
 
         meta
     }
+
+    /// Returns true if `now` is inside the publication window of the page
+    pub fn is_live(&self, now: DateTime<Utc>) -> bool {
+        self.publish_date
+            .is_none_or(|publish_date| publish_date <= now)
+            && self.expiry_date.is_none_or(|expiry_date| now < expiry_date)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +116,11 @@ pub struct PageEntry {
     pub sitemap_meta: SitemapMeta,
     pub frontmatter: Option<Value>,
     pub content: Option<String>,
+    /// Whether the frontmatter marks this page as a draft
+    pub draft: bool,
+    /// Folders between the collection directory and the source file of a
+    /// collection item, outermost first
+    pub sections: Vec<String>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -93,6 +162,25 @@ impl SitePages {
             .collect()
     }
 
+    pub fn collection_lists(&self) -> Vec<&PageEntry> {
+        self.pages
+            .iter()
+            .filter(|p| matches!(p.page_type, PageType::CollectionList { .. }))
+            .collect()
+    }
+
+    pub fn taxonomy_pages(&self) -> Vec<&PageEntry> {
+        self.pages
+            .iter()
+            .filter(|p| p.page_type.taxonomy_name().is_some())
+            .collect()
+    }
+
+    /// Pages listed in the sitemap, which are all pages not excluded by
+    /// their frontmatter
+    ///
+    /// Drafts are only discovered by builds with drafts enabled, whose sitemap
+    /// then lists them like the rest of the preview.
     pub fn sitemap_pages(&self) -> Vec<&PageEntry> {
         self.pages
             .iter()
@@ -184,6 +272,22 @@ mod tests {
         assert!(meta.exclude);
     }
 
+    #[test]
+    fn test_sitemap_meta_publication_window() {
+        let fm = serde_json::json!({
+            "publish_date": "2024-01-20T00:00:00Z",
+            "expiry_date": "2024-02-20T00:00:00Z"
+        });
+        let meta = SitemapMeta::from_frontmatter(&fm);
+        let date = |s| crate::date::parse_date(s).unwrap();
+
+        assert!(!meta.is_live(date("2024-01-19T23:59:59Z")));
+        assert!(meta.is_live(date("2024-01-20T00:00:00Z")));
+        assert!(meta.is_live(date("2024-02-19T00:00:00Z")));
+        assert!(!meta.is_live(date("2024-02-20T00:00:00Z")));
+        assert!(SitemapMeta::default().is_live(date("2024-01-01")));
+    }
+
     #[test]
     fn test_site_pages_filtering() {
         let mut site_pages = SitePages::new();
@@ -196,6 +300,8 @@ mod tests {
                 sitemap_meta: SitemapMeta::default(),
                 frontmatter: None,
                 content: None,
+                draft: false,
+                sections: Vec::new(),
             },
             PageEntry {
                 url_path: "/blog/post-1".to_string(),
@@ -207,6 +313,8 @@ mod tests {
                 sitemap_meta: SitemapMeta::default(),
                 frontmatter: Some(serde_json::json!({"title": "Post 1"})),
                 content: Some("<p>Content</p>".to_string()),
+                draft: false,
+                sections: Vec::new(),
             },
         ]);
 
@@ -230,9 +338,12 @@ mod tests {
                     priority: Some(1.0),
                     changefreq: Some("daily".to_string()),
                     exclude: false,
+                    ..Default::default()
                 },
                 frontmatter: None,
                 content: None,
+                draft: false,
+                sections: Vec::new(),
             },
             PageEntry {
                 url_path: "/about".to_string(),
@@ -245,6 +356,8 @@ mod tests {
                 },
                 frontmatter: None,
                 content: None,
+                draft: false,
+                sections: Vec::new(),
             },
         ]);
 
@@ -259,6 +372,26 @@ mod tests {
         assert!(!xml.contains("/about"));
     }
 
+    #[test]
+    fn test_sitemap_lists_discovered_drafts() {
+        let mut site_pages = SitePages::new();
+        site_pages.add_pages(vec![PageEntry {
+            url_path: "/blog/draft".to_string(),
+            source_path: PathBuf::from("content/blog/draft.md"),
+            output_path: PathBuf::from("dist/blog/draft.html"),
+            page_type: PageType::Collection {
+                name: "blog".to_string(),
+            },
+            sitemap_meta: SitemapMeta::default(),
+            frontmatter: Some(serde_json::json!({"draft": true})),
+            content: Some("<p>Content</p>".to_string()),
+            draft: true,
+            sections: Vec::new(),
+        }]);
+
+        assert_eq!(site_pages.sitemap_pages().len(), 1);
+    }
+
     #[test]
     fn test_escape_xml() {
         assert_eq!(escape_xml("Hello & World"), "Hello &amp; World");
@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::config::InitFeature;
+
+/// Starter site that `balzac init --template` can generate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StarterTemplate {
+    Blog,
+    Docs,
+    Portfolio,
+}
+
+impl StarterTemplate {
+    pub const NAMES: [&'static str; 3] = ["blog", "docs", "portfolio"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "blog" => Some(StarterTemplate::Blog),
+            "docs" => Some(StarterTemplate::Docs),
+            "portfolio" => Some(StarterTemplate::Portfolio),
+            _ => None,
+        }
+    }
+
+    /// Name given to the site in the generated configuration
+    fn site_name(&self) -> &'static str {
+        match self {
+            StarterTemplate::Blog => "My Blog",
+            StarterTemplate::Docs => "My Docs",
+            StarterTemplate::Portfolio => "My Portfolio",
+        }
+    }
+
+    /// Global data the files of the template rely on, to be written with
+    /// [`Config::create_with_global`](crate::config::Config::create_with_global)
+    pub fn global(&self) -> HashMap<String, Value> {
+        HashMap::from([("site_name".to_string(), Value::from(self.site_name()))])
+    }
+
+    fn files(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            StarterTemplate::Blog => BLOG_FILES,
+            StarterTemplate::Docs => DOCS_FILES,
+            StarterTemplate::Portfolio => PORTFOLIO_FILES,
+        }
+    }
+}
+
+/// Writes the files of `template`, and of the features that need more than
+/// configuration, into `root`
+///
+/// The configuration is not part of these files, see
+/// [`StarterTemplate::global`]. Existing files are left untouched. Returns the
+/// paths of the created files.
+pub fn write_files(
+    root: &Path,
+    template: Option<StarterTemplate>,
+    features: &[InitFeature],
+) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<(&str, &str)> = Vec::new();
+
+    if let Some(template) = template {
+        files.extend_from_slice(template.files());
+        files.extend_from_slice(SHARED_FILES);
+
+        let scripts = if features.contains(&InitFeature::Vite) {
+            VITE_SCRIPTS_PARTIAL
+        } else {
+            ""
+        };
+        files.push(("partials/scripts.hbs", scripts));
+    }
+
+    if features.contains(&InitFeature::Vite) {
+        files.extend_from_slice(VITE_FILES);
+    }
+
+    let mut created = Vec::new();
+    for (relative_path, content) in files {
+        let path = root.join(relative_path);
+        if fs::exists(&path)? {
+            log::warn!("{} already exists, skipping", path.display());
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content)?;
+        log::debug!("Created {}", path.display());
+        created.push(path);
+    }
+
+    Ok(created)
+}
+
+const VITE_SCRIPTS_PARTIAL: &str = r#"<script type="module" src="/{{vite_url "main.js"}}"></script>
+"#;
+
+const VITE_FILES: &[(&str, &str)] = &[
+    (
+        "package.json",
+        r#"{
+  "type": "module",
+  "private": true,
+  "scripts": {
+    "build": "vite build"
+  },
+  "dependencies": {
+    "vite": "^7.3.1"
+  }
+}
+"#,
+    ),
+    (
+        "vite.config.js",
+        r#"import { defineConfig } from "vite";
+export default defineConfig({
+  build: {
+    // generate .vite/manifest.json in outDir
+    manifest: true,
+    rollupOptions: {
+      // overwrite default .html entry
+      input: "main.js",
+    },
+  },
+});
+"#,
+    ),
+    ("main.js", "console.log(\"Hello from balzac\");\n"),
+];
+
+const SHARED_FILES: &[(&str, &str)] = &[
+    (
+        "layouts/base.hbs",
+        r#"<!DOCTYPE html>
+<html lang="en">
+    <head>
+        <meta charset="utf-8">
+        <meta name="viewport" content="width=device-width, initial-scale=1">
+        <title>{{#if fm.title}}{{fm.title}} | {{/if}}{{site_name}}</title>
+        <link rel="stylesheet" href="/assets/style.css">
+    </head>
+    <body>
+        {{> header}}
+        <main>
+            {{> @partial-block}}
+        </main>
+        {{> footer}}
+        {{> scripts}}
+    </body>
+</html>
+"#,
+    ),
+    (
+        "partials/footer.hbs",
+        r#"<footer>
+    <p>Built with <a href="https://balzac.rs">balzac</a></p>
+</footer>
+"#,
+    ),
+    (
+        "assets/style.css",
+        r#"body {
+    font-family: system-ui, sans-serif;
+    line-height: 1.6;
+    max-width: 48rem;
+    margin: 0 auto;
+    padding: 1rem;
+}
+
+header nav a {
+    margin-right: 1rem;
+}
+
+footer {
+    margin-top: 3rem;
+    color: #666;
+}
+"#,
+    ),
+];
+
+const BLOG_FILES: &[(&str, &str)] = &[
+    (
+        "partials/header.hbs",
+        r#"<header>
+    <nav>
+        <a href="/">{{site_name}}</a>
+        <a href="/posts/hello-world">Latest post</a>
+    </nav>
+</header>
+"#,
+    ),
+    (
+        "pages/index.hbs",
+        r#"{{#> base}}
+<h1>Welcome to {{site_name}}</h1>
+<p>This is the home page of your new blog. Edit <code>pages/index.hbs</code> to change it.</p>
+<ul>
+    <li><a href="/posts/hello-world">Hello, World!</a></li>
+</ul>
+{{/base}}
+"#,
+    ),
+    (
+        "pages/posts/details.hbs",
+        r#"{{#> base}}
+<article>
+    <h1>{{fm.title}}</h1>
+    {{#if fm.date}}<time datetime="{{fm.date}}">{{fm.date}}</time>{{/if}}
+    {{{content}}}
+</article>
+{{/base}}
+"#,
+    ),
+    (
+        "content/posts/hello-world.md",
+        r#"---
+title: "Hello, World!"
+date: 2026-01-01
+---
+
+This is your first post. Create new ones with `balzac new posts "My next post"`.
+"#,
+    ),
+    (
+        "archetypes/posts.md",
+        r#"---
+title: "{{title}}"
+date: {{date}}
+---
+
+"#,
+    ),
+];
+
+const DOCS_FILES: &[(&str, &str)] = &[
+    (
+        "partials/header.hbs",
+        r#"<header>
+    <nav>
+        <a href="/">{{site_name}}</a>
+        <a href="/docs/getting-started">Getting started</a>
+        <a href="/docs/configuration">Configuration</a>
+    </nav>
+</header>
+"#,
+    ),
+    (
+        "pages/index.hbs",
+        r#"{{#> base}}
+<h1>{{site_name}}</h1>
+<p>Welcome to the documentation. Start with the <a href="/docs/getting-started">getting started guide</a>.</p>
+{{/base}}
+"#,
+    ),
+    (
+        "pages/docs/details.hbs",
+        r#"{{#> base}}
+<article>
+    <h1>{{fm.title}}</h1>
+    {{#if fm.description}}<p><em>{{fm.description}}</em></p>{{/if}}
+    {{{content}}}
+</article>
+{{/base}}
+"#,
+    ),
+    (
+        "content/docs/getting-started.md",
+        r#"---
+title: "Getting started"
+description: "Install the project and run it for the first time"
+---
+
+## Installation
+
+Describe how to install your project here.
+"#,
+    ),
+    (
+        "content/docs/configuration.md",
+        r#"---
+title: "Configuration"
+description: "Every available option"
+---
+
+| Option | Description |
+| ------ | ----------- |
+| `name` | The name of the thing |
+"#,
+    ),
+    (
+        "archetypes/docs.md",
+        r#"---
+title: "{{title}}"
+description: ""
+---
+
+"#,
+    ),
+];
+
+const PORTFOLIO_FILES: &[(&str, &str)] = &[
+    (
+        "partials/header.hbs",
+        r#"<header>
+    <nav>
+        <a href="/">{{site_name}}</a>
+        <a href="/projects/first-project">Projects</a>
+    </nav>
+</header>
+"#,
+    ),
+    (
+        "pages/index.hbs",
+        r#"{{#> base}}
+<h1>Hi, welcome to {{site_name}}</h1>
+<p>A short introduction about who you are and what you do.</p>
+<h2>Selected work</h2>
+<ul>
+    <li><a href="/projects/first-project">First project</a></li>
+</ul>
+{{/base}}
+"#,
+    ),
+    (
+        "pages/projects/details.hbs",
+        r#"{{#> base}}
+<article>
+    <h1>{{fm.title}}</h1>
+    {{#if fm.role}}<p>Role: {{fm.role}}</p>{{/if}}
+    {{#if fm.date}}<p>Date: {{fm.date}}</p>{{/if}}
+    {{{content}}}
+</article>
+{{/base}}
+"#,
+    ),
+    (
+        "content/projects/first-project.md",
+        r#"---
+title: "First project"
+role: "Design and development"
+date: 2026-01-01
+---
+
+Describe the project, the problem it solved and the result.
+"#,
+    ),
+    (
+        "archetypes/projects.md",
+        r#"---
+title: "{{title}}"
+role: ""
+date: {{date}}
+---
+
+"#,
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_from_name() {
+        for name in StarterTemplate::NAMES {
+            assert!(StarterTemplate::from_name(name).is_some());
+        }
+        assert_eq!(StarterTemplate::from_name("wiki"), None);
+    }
+
+    #[test]
+    fn test_write_files_keeps_existing_files() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("pages")).unwrap();
+        fs::write(temp_dir.path().join("pages/index.hbs"), "mine").unwrap();
+
+        let created = write_files(temp_dir.path(), Some(StarterTemplate::Blog), &[]).unwrap();
+
+        assert!(!created.contains(&temp_dir.path().join("pages/index.hbs")));
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("pages/index.hbs")).unwrap(),
+            "mine"
+        );
+        assert!(temp_dir.path().join("layouts/base.hbs").exists());
+        assert!(!temp_dir.path().join("balzac.toml").exists());
+    }
+
+    #[test]
+    fn test_write_files_for_vite_feature() {
+        let temp_dir = TempDir::new().unwrap();
+
+        write_files(temp_dir.path(), None, &[InitFeature::Vite]).unwrap();
+
+        assert!(temp_dir.path().join("package.json").exists());
+        assert!(temp_dir.path().join("vite.config.js").exists());
+        assert!(temp_dir.path().join("main.js").exists());
+        assert!(!temp_dir.path().join("pages/index.hbs").exists());
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::archetype::slugify;
+use crate::sitemap::PageEntry;
+
+/// A term of a taxonomy, like a single tag, with the items classified in it
+#[derive(Debug, Clone)]
+pub struct Term<'a> {
+    /// The term as first written in frontmatter
+    pub name: String,
+    /// The slugified term, used in URLs
+    pub slug: String,
+    pub items: Vec<&'a PageEntry>,
+}
+
+/// Reads the terms stored under `field` in `frontmatter`, either a single
+/// string or a list of strings
+pub fn item_terms(frontmatter: &Value, field: &str) -> Result<Vec<String>, String> {
+    let terms = match frontmatter.get(field) {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::String(term)) => vec![term.clone()],
+        Some(Value::Array(values)) if values.iter().all(Value::is_string) => values
+            .iter()
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect(),
+        Some(value) => {
+            return Err(format!(
+                "invalid {} {}, expected a string or a list of strings",
+                field, value
+            ));
+        }
+    };
+
+    match terms.iter().find(|term| slugify(term).is_empty()) {
+        Some(term) => Err(format!(
+            "invalid {} term {:?}, expected at least one letter or digit",
+            field, term
+        )),
+        None => Ok(terms),
+    }
+}
+
+/// Groups `items` by the terms stored under their `field` frontmatter field,
+/// terms being sorted by slug and their items kept in order
+///
+/// Terms with the same slug, like `Rust` and `rust`, are merged under the
+/// first spelling found. Invalid terms are ignored, see [`item_terms`].
+pub fn group_terms<'a>(items: &[&'a PageEntry], field: &str) -> Vec<Term<'a>> {
+    let mut terms: BTreeMap<String, Term<'a>> = BTreeMap::new();
+    // Items already listed under each term, an item listing the same term
+    // twice, like `[Rust, rust]`, being listed once
+    let mut listed: HashSet<(String, &Path)> = HashSet::new();
+
+    for item in items {
+        let Some(frontmatter) = &item.frontmatter else {
+            continue;
+        };
+        for name in item_terms(frontmatter, field).unwrap_or_default() {
+            let slug = slugify(&name);
+            if !listed.insert((slug.clone(), item.output_path.as_path())) {
+                continue;
+            }
+            terms
+                .entry(slug.clone())
+                .or_insert_with(|| Term {
+                    name,
+                    slug,
+                    items: Vec::new(),
+                })
+                .items
+                .push(item);
+        }
+    }
+
+    terms.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sitemap::{PageType, SitemapMeta};
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn item(name: &str, frontmatter: Value) -> PageEntry {
+        PageEntry {
+            url_path: format!("/posts/{}", name),
+            source_path: PathBuf::from(format!("content/posts/{}.md", name)),
+            output_path: PathBuf::from(format!("dist/posts/{}.html", name)),
+            page_type: PageType::Collection {
+                name: "posts".to_string(),
+            },
+            sitemap_meta: SitemapMeta::default(),
+            frontmatter: Some(frontmatter),
+            content: None,
+            draft: false,
+            sections: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_item_terms() {
+        assert_eq!(
+            item_terms(&json!({"tags": ["Rust", "Web"]}), "tags").unwrap(),
+            vec!["Rust", "Web"]
+        );
+        assert_eq!(
+            item_terms(&json!({"category": "News"}), "category").unwrap(),
+            vec!["News"]
+        );
+        assert!(item_terms(&json!({}), "tags").unwrap().is_empty());
+        assert!(item_terms(&json!({"tags": [1, 2]}), "tags").is_err());
+        assert!(item_terms(&json!({"tags": "!!!"}), "tags").is_err());
+    }
+
+    #[test]
+    fn test_group_terms() {
+        let first = item("first", json!({"tags": ["Rust", "Web Dev"]}));
+        let second = item("second", json!({"tags": ["rust", "Rust"]}));
+        let untagged = item("untagged", json!({}));
+        let items = vec![&first, &second, &untagged];
+
+        let terms = group_terms(&items, "tags");
+        let summary: Vec<(&str, &str, usize)> = terms
+            .iter()
+            .map(|term| (term.name.as_str(), term.slug.as_str(), term.items.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("Rust", "rust", 2), ("Web Dev", "web-dev", 1)]
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::config::{self, ResolvedConfig};
+
+/// Time to wait for more events before triggering a rebuild, so that a
+/// single save (which often emits several events) only rebuilds once
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Set of source locations that should trigger a rebuild when they change
+pub struct WatchTargets {
+    directories: Vec<PathBuf>,
+    config_files: Vec<PathBuf>,
+}
+
+impl WatchTargets {
+    /// Collects every source directory of the project plus its balzac.toml
+    /// and the overlay file of the active environment
+    ///
+    /// Targets are canonicalized, since that is how the watcher reports the
+    /// paths of events.
+    pub fn from_config(configuration: &ResolvedConfig) -> Self {
+        let mut config_files = vec![configuration.root_directory.join("balzac.toml")];
+        if let Some(environment) = &configuration.environment {
+            config_files.push(
+                configuration
+                    .root_directory
+                    .join(config::overlay_filename(environment)),
+            );
+        }
+
+        let mut directories = vec![
+            configuration.pages_directory.clone(),
+            configuration.partials_directory.clone(),
+            configuration.layouts_directory.clone(),
+            configuration.content_directory.clone(),
+            configuration.assets_directory.clone(),
+        ];
+        directories.extend(
+            configuration
+                .static_mounts
+                .iter()
+                .map(|mount| mount.from.clone()),
+        );
+
+        Self {
+            directories: directories.iter().map(|dir| canonicalize(dir)).collect(),
+            config_files: config_files.iter().map(|file| canonicalize(file)).collect(),
+        }
+    }
+
+    /// Returns true if a change to `path` should trigger a rebuild
+    fn is_relevant(&self, path: &Path) -> bool {
+        self.config_files.iter().any(|file| path == file)
+            || self.directories.iter().any(|dir| path.starts_with(dir))
+    }
+}
+
+/// Canonicalizes `path`, which may not exist yet, by canonicalizing its
+/// closest existing ancestor
+fn canonicalize(path: &Path) -> PathBuf {
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            canonicalize(parent).join(name)
+        }
+        _ => std::env::current_dir()
+            .map(|current| current.join(path))
+            .unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+/// Watches the given targets and calls `on_change` with the changed paths
+/// every time a batch of relevant changes is detected
+///
+/// `on_change` returns the targets to watch from then on, if they may have
+/// changed, like after an edit of balzac.toml. Errors reported by the watcher
+/// are logged and watching continues. Blocks the current thread for as long
+/// as the watcher is alive.
+pub fn watch<F>(mut targets: WatchTargets, mut on_change: F) -> notify::Result<()>
+where
+    F: FnMut(&[PathBuf]) -> Option<WatchTargets>,
+{
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let mut watches = Watches::default();
+    watches.sync(&mut watcher, &targets);
+
+    while let Ok(event) = rx.recv() {
+        let mut changed = Vec::new();
+        collect_changes(&targets, event, &mut changed);
+
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect_changes(&targets, event, &mut changed);
+        }
+
+        // Directories created since the last batch, like a first content
+        // directory, are watched from now on and their files built right away
+        for dir in watches.sync(&mut watcher, &targets) {
+            if !changed.contains(&dir) {
+                changed.push(dir);
+            }
+        }
+
+        if !changed.is_empty()
+            && let Some(new_targets) = on_change(&changed)
+        {
+            targets = new_targets;
+            watches.sync(&mut watcher, &targets);
+        }
+    }
+
+    Ok(())
+}
+
+fn collect_changes(
+    targets: &WatchTargets,
+    event: notify::Result<notify::Event>,
+    changed: &mut Vec<PathBuf>,
+) {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            log::error!("Error watching files: {}", e);
+            return;
+        }
+    };
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+
+    for path in event.paths {
+        if targets.is_relevant(&path) && !changed.contains(&path) {
+            changed.push(path);
+        }
+    }
+}
+
+/// Paths registered with the watcher, along with how they are watched
+#[derive(Default)]
+struct Watches {
+    watched: HashMap<PathBuf, RecursiveMode>,
+}
+
+impl Watches {
+    /// Registers the paths needed to notice changes to `targets` with
+    /// `watcher` and drops the others, returning the target directories that
+    /// started being watched
+    ///
+    /// Target directories that do not exist yet are noticed through their
+    /// closest existing ancestor, and config files through their directory,
+    /// since editors usually replace files on save, which drops a watch placed
+    /// on the file itself.
+    fn sync(&mut self, watcher: &mut impl Watcher, targets: &WatchTargets) -> Vec<PathBuf> {
+        let mut wanted: HashMap<PathBuf, RecursiveMode> = HashMap::new();
+        for file in &targets.config_files {
+            if let Some(parent) = file.parent() {
+                wanted.insert(parent.to_path_buf(), RecursiveMode::NonRecursive);
+            }
+        }
+        for dir in &targets.directories {
+            if dir.is_dir() {
+                wanted.insert(dir.clone(), RecursiveMode::Recursive);
+            } else if let Some(ancestor) = dir.ancestors().skip(1).find(|path| path.is_dir()) {
+                wanted
+                    .entry(ancestor.to_path_buf())
+                    .or_insert(RecursiveMode::NonRecursive);
+            }
+        }
+
+        self.watched.retain(|path, mode| {
+            let keep = wanted.get(path) == Some(mode);
+            if !keep {
+                // The path may be gone already, along with its watch
+                let _ = watcher.unwatch(path);
+            }
+            keep
+        });
+
+        let mut started = Vec::new();
+        for (path, mode) in wanted {
+            if self.watched.contains_key(&path) {
+                continue;
+            }
+            match watcher.watch(&path, mode) {
+                Ok(()) => {
+                    log::debug!("Watching {}", path.display());
+                    if mode == RecursiveMode::Recursive {
+                        started.push(path.clone());
+                    }
+                    self.watched.insert(path, mode);
+                }
+                Err(e) => log::warn!("Could not watch {}: {}", path.display(), e),
+            }
+        }
+        started
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    use crate::config::Config;
+
+    fn targets() -> WatchTargets {
+        WatchTargets {
+            directories: vec![PathBuf::from("/site/pages"), PathBuf::from("/site/content")],
+            config_files: vec![
+                PathBuf::from("/site/balzac.toml"),
+                PathBuf::from("/site/balzac.production.toml"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_source_files_are_relevant() {
+        let targets = targets();
+        assert!(targets.is_relevant(Path::new("/site/pages/index.hbs")));
+        assert!(targets.is_relevant(Path::new("/site/content/posts/a.md")));
+        assert!(targets.is_relevant(Path::new("/site/balzac.toml")));
+        assert!(targets.is_relevant(Path::new("/site/balzac.production.toml")));
+    }
+
+    #[test]
+    fn test_output_files_are_not_relevant() {
+        let targets = targets();
+        assert!(!targets.is_relevant(Path::new("/site/dist/index.html")));
+        assert!(!targets.is_relevant(Path::new("/site/dist")));
+        assert!(!targets.is_relevant(Path::new("/site/package.json")));
+        assert!(!targets.is_relevant(Path::new("/site/balzac.staging.toml")));
+    }
+
+    #[test]
+    fn test_targets_are_canonicalized() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("pages")).unwrap();
+
+        // The same project root, relative to the current directory
+        let current_dir = std::env::current_dir().unwrap();
+        let mut root = PathBuf::new();
+        for _ in current_dir.components().skip(1) {
+            root.push("..");
+        }
+        root.push(temp_dir.path().strip_prefix("/").unwrap());
+        assert!(root.is_relative());
+
+        let targets = WatchTargets::from_config(&Config::default().resolve(&root));
+        let canonical_root = temp_dir.path().canonicalize().unwrap();
+        assert!(targets.is_relevant(&canonical_root.join("pages/index.hbs")));
+        assert!(targets.is_relevant(&canonical_root.join("content/posts/a.md")));
+        assert!(targets.is_relevant(&canonical_root.join("balzac.toml")));
+        assert!(!targets.is_relevant(&canonical_root.join("dist/index.html")));
+    }
+
+    #[test]
+    fn test_error_events_are_skipped() {
+        let mut changed = Vec::new();
+        collect_changes(
+            &targets(),
+            Err(notify::Error::generic("watch limit reached")),
+            &mut changed,
+        );
+        collect_changes(
+            &targets(),
+            Ok(notify::Event::new(EventKind::Any).add_path(PathBuf::from("/site/pages/a.hbs"))),
+            &mut changed,
+        );
+        assert_eq!(changed, vec![PathBuf::from("/site/pages/a.hbs")]);
+    }
+
+    #[test]
+    fn test_missing_directories_are_watched_once_created() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::create_dir(root.join("pages")).unwrap();
+        let targets = WatchTargets::from_config(&Config::default().resolve(&root));
+
+        let (tx, _rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).unwrap();
+        let mut watches = Watches::default();
+        assert_eq!(
+            watches.sync(&mut watcher, &targets),
+            vec![root.join("pages")]
+        );
+        assert_eq!(
+            watches.watched.get(&root),
+            Some(&RecursiveMode::NonRecursive)
+        );
+        assert!(watches.sync(&mut watcher, &targets).is_empty());
+
+        fs::create_dir(root.join("content")).unwrap();
+        assert_eq!(
+            watches.sync(&mut watcher, &targets),
+            vec![root.join("content")]
+        );
+
+        // Directories that are no longer targets stop being watched
+        let targets = WatchTargets {
+            directories: vec![root.join("content")],
+            config_files: Vec::new(),
+        };
+        watches.sync(&mut watcher, &targets);
+        assert_eq!(
+            watches.watched.keys().collect::<Vec<_>>(),
+            vec![&root.join("content")]
+        );
+    }
+}
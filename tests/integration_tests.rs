@@ -1,11 +1,14 @@
+use balzac::build::{BuildError, Builder};
+use balzac::check;
 use balzac::renderer::{HandlebarsRenderer, Renderer};
 use balzac::sitemap::SitePages;
+use balzac::starter::{self, StarterTemplate};
 use std::fs;
 use std::path::PathBuf;
 use tempfile::TempDir;
 
 // Import from the main crate
-use balzac::config::{Config, SitemapConfig};
+use balzac::config::{Config, Hooks, SitemapConfig, UrlStyle};
 use balzac::{
     discover_collections, discover_static_pages, make_dist_folder, render_collection_items,
     render_pages, write_sitemap,
@@ -77,11 +80,14 @@ fn test_partials_registration() {
         bundler: None,
         base_url: None,
         sitemap: None,
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&temp_path);
     let mut renderer = HandlebarsRenderer::new(&resolved_config);
-    renderer.init(&resolved_config);
+    renderer
+        .init(&resolved_config)
+        .expect("Failed to initialize renderer");
 
     assert!(
         renderer.registry.get_templates().contains_key("alert"),
@@ -114,14 +120,17 @@ fn test_partials_registration_without_folder() {
         bundler: None,
         base_url: None,
         sitemap: None,
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&_temp_path);
     let mut renderer = HandlebarsRenderer::new(&resolved_config);
-    renderer.init(&resolved_config);
+    renderer
+        .init(&resolved_config)
+        .expect("Failed to initialize renderer");
 
     assert!(
-        renderer.registry.get_templates().len() == 0,
+        renderer.registry.get_templates().is_empty(),
         "Could not register partial alert"
     );
 }
@@ -157,6 +166,7 @@ fn test_full_workflow_single_page() {
         bundler: None,
         base_url: None,
         sitemap: None,
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&_temp_path);
@@ -216,6 +226,7 @@ fn test_full_workflow_multiple_pages() {
         bundler: None,
         base_url: None,
         sitemap: None,
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&_temp_path);
@@ -285,6 +296,7 @@ fn test_workflow_with_global_data() {
         bundler: None,
         base_url: None,
         sitemap: None,
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&_temp_path);
@@ -333,6 +345,7 @@ fn test_make_dist_folder_creates_directory() {
         bundler: None,
         base_url: None,
         sitemap: None,
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&temp_path);
@@ -371,6 +384,7 @@ fn test_make_dist_folder_recreates_existing_directory() {
         bundler: None,
         base_url: None,
         sitemap: None,
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&_temp_path);
@@ -411,6 +425,7 @@ fn test_workflow_preserves_file_extensions() {
         bundler: None,
         base_url: None,
         sitemap: None,
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&_temp_path);
@@ -462,6 +477,7 @@ fn test_template_with_conditionals() {
         bundler: None,
         base_url: None,
         sitemap: None,
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&_temp_path);
@@ -515,6 +531,7 @@ fn test_sitemap_generation_with_static_pages() {
             default_priority: Some(0.5),
             default_changefreq: None,
         }),
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&_temp_path);
@@ -535,7 +552,7 @@ fn test_sitemap_generation_with_static_pages() {
     // Render phase
     let renderer = HandlebarsRenderer::new(&resolved_config);
     render_pages(&resolved_config, site_pages.all(), &renderer).expect("Failed to render pages");
-    render_collection_items(&resolved_config, site_pages.all(), &renderer)
+    render_collection_items(&resolved_config, site_pages.all(), &site_pages, &renderer)
         .expect("Failed to render collections");
 
     // Sitemap generation
@@ -611,6 +628,7 @@ fn test_sitemap_generation_with_collections() {
             default_priority: None,
             default_changefreq: None,
         }),
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&temp_path);
@@ -637,7 +655,7 @@ fn test_sitemap_generation_with_collections() {
     // Render phase
     let renderer = HandlebarsRenderer::new(&resolved_config);
     render_pages(&resolved_config, site_pages.all(), &renderer).expect("Failed to render pages");
-    render_collection_items(&resolved_config, site_pages.all(), &renderer)
+    render_collection_items(&resolved_config, site_pages.all(), &site_pages, &renderer)
         .expect("Failed to render collections");
 
     // Sitemap generation
@@ -706,6 +724,7 @@ fn test_sitemap_excludes_pages_with_sitemap_exclude() {
         bundler: None,
         base_url: Some("https://example.com".to_string()),
         sitemap: Some(SitemapConfig::default()),
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&temp_path);
@@ -718,7 +737,7 @@ fn test_sitemap_excludes_pages_with_sitemap_exclude() {
     site_pages.add_pages(collection_pages);
 
     let renderer = HandlebarsRenderer::new(&resolved_config);
-    render_collection_items(&resolved_config, site_pages.all(), &renderer)
+    render_collection_items(&resolved_config, site_pages.all(), &site_pages, &renderer)
         .expect("Failed to render collections");
 
     write_sitemap(&resolved_config, &site_pages).expect("Failed to write sitemap");
@@ -759,6 +778,7 @@ fn test_no_sitemap_without_base_url() {
         bundler: None,
         base_url: None, // No base_url configured
         sitemap: None,
+        ..Default::default()
     };
 
     let resolved_config = config.resolve(&temp_path);
@@ -780,3 +800,1045 @@ fn test_no_sitemap_without_base_url() {
         "Sitemap should not be created without base_url"
     );
 }
+
+#[test]
+fn test_collection_items_render_in_parallel_with_stable_order() {
+    let (
+        _temp,
+        temp_path,
+        pages_dir,
+        output_dir,
+        layouts_dir,
+        partials_dir,
+        assets_dir,
+        content_dir,
+    ) = setup_test_project();
+
+    let blog_pages_dir = pages_dir.join("blog");
+    let blog_content_dir = content_dir.join("blog");
+    fs::create_dir(&blog_pages_dir).expect("Failed to create blog pages dir");
+    fs::create_dir_all(&blog_content_dir).expect("Failed to create blog content dir");
+
+    fs::write(
+        blog_pages_dir.join("details.hbs"),
+        "<h1>{{fm.title}}</h1>{{{content}}}",
+    )
+    .expect("Failed to write details template");
+
+    for i in (0..50).rev() {
+        fs::write(
+            blog_content_dir.join(format!("post-{:02}.md", i)),
+            format!("---\ntitle: Post {}\n---\n\nBody {}", i, i),
+        )
+        .expect("Failed to write post");
+    }
+
+    let config = Config {
+        output_directory: output_dir.to_string_lossy().to_string(),
+        pages_directory: pages_dir.to_string_lossy().to_string(),
+        layouts_directory: layouts_dir.to_string_lossy().to_string(),
+        partials_directory: partials_dir.to_string_lossy().to_string(),
+        assets_directory: assets_dir.to_string_lossy().to_string(),
+        content_directory: content_dir.to_string_lossy().to_string(),
+        global: None,
+        hooks: None,
+        bundler: None,
+        base_url: None,
+        sitemap: None,
+        ..Default::default()
+    };
+
+    let resolved_config = config.resolve(&temp_path);
+    make_dist_folder(&resolved_config).expect("Failed to make dist folder");
+
+    let collection_pages =
+        discover_collections(&resolved_config).expect("Failed to discover collections");
+    let urls: Vec<&str> = collection_pages
+        .iter()
+        .map(|p| p.url_path.as_str())
+        .collect();
+    let expected: Vec<String> = (0..50).map(|i| format!("/blog/post-{:02}", i)).collect();
+    assert_eq!(
+        urls, expected,
+        "Items should be discovered in file name order"
+    );
+
+    let mut site_pages = SitePages::new();
+    site_pages.add_pages(collection_pages.clone());
+
+    let renderer = HandlebarsRenderer::new(&resolved_config);
+    render_collection_items(&resolved_config, &collection_pages, &site_pages, &renderer)
+        .expect("Failed to render collections");
+
+    for i in 0..50 {
+        let output =
+            fs::read_to_string(output_dir.join("blog").join(format!("post-{:02}.html", i)))
+                .expect("Failed to read rendered post");
+        assert!(output.starts_with(&format!("<h1>Post {}</h1>", i)));
+    }
+}
+
+#[test]
+fn test_builder_returns_report() {
+    let (_temp, temp_path, pages_dir, output_dir, _, _, _, content_dir) = setup_test_project();
+
+    fs::write(pages_dir.join("index.hbs"), "<h1>Home</h1>").expect("Failed to write index");
+    fs::create_dir(pages_dir.join("blog")).expect("Failed to create blog pages dir");
+    fs::write(pages_dir.join("blog").join("details.hbs"), "{{fm.title}}")
+        .expect("Failed to write details template");
+    fs::create_dir_all(content_dir.join("blog")).expect("Failed to create blog content dir");
+    fs::write(
+        content_dir.join("blog").join("post.md"),
+        "---\ntitle: Post\n---\n\nBody",
+    )
+    .expect("Failed to write post");
+
+    let report = Builder::new(Config::default(), &temp_path)
+        .build()
+        .expect("Build should succeed");
+
+    assert_eq!(report.site_pages.all().len(), 2);
+    assert_eq!(report.rendered_pages, 2);
+    assert_eq!(
+        fs::read_to_string(output_dir.join("blog").join("post.html")).unwrap(),
+        "Post"
+    );
+
+    // A second build reuses the cache and renders nothing
+    let report = Builder::new(Config::default(), &temp_path)
+        .build()
+        .expect("Build should succeed");
+    assert_eq!(report.rendered_pages, 0);
+}
+
+#[test]
+fn test_builder_returns_render_error() {
+    let (_temp, temp_path, pages_dir, _, _, _, _, _) = setup_test_project();
+
+    fs::write(pages_dir.join("index.hbs"), "{{#if open}}never closed")
+        .expect("Failed to write index");
+
+    let result = Builder::new(Config::default(), &temp_path).build();
+    assert!(matches!(result, Err(BuildError::Render(_))));
+}
+
+#[test]
+fn test_builder_returns_hook_error() {
+    let (_temp, temp_path, _, _, _, _, _, _) = setup_test_project();
+
+    let config = Config {
+        hooks: Some(Hooks {
+            build_before: Some("false".to_string()),
+            build_after: None,
+            render_init_before: None,
+            render_init_after: None,
+            render_before: None,
+            render_after: None,
+        }),
+        ..Default::default()
+    };
+
+    let result = Builder::new(config, &temp_path).build();
+    assert!(matches!(result, Err(BuildError::Hook(_))));
+}
+
+#[test]
+fn test_builder_from_root_without_config() {
+    let (_temp, temp_path, _, _, _, _, _, _) = setup_test_project();
+
+    let result = Builder::from_root(&temp_path);
+    assert!(matches!(result, Err(BuildError::Config(_))));
+}
+
+#[test]
+fn test_starter_templates_build_immediately() {
+    for name in StarterTemplate::NAMES {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let root = temp_dir.path();
+
+        let template = StarterTemplate::from_name(name);
+        Config::create_with_global(root, None, template.map(|template| template.global()))
+            .expect("Failed to create config");
+        starter::write_files(root, template, &[]).expect("Failed to write starter files");
+
+        let problems = check::check(root, None);
+        assert!(problems.is_empty(), "{}: {:?}", name, problems);
+
+        let report = Builder::from_root(root)
+            .expect("Failed to load config")
+            .build()
+            .expect("Failed to build starter site");
+        assert!(report.site_pages.all().len() >= 2, "{}", name);
+
+        let index = fs::read_to_string(root.join("dist/index.html")).unwrap();
+        assert!(index.contains("<footer>"), "{}", name);
+        assert!(index.contains("My "), "{}", name);
+    }
+}
+
+#[test]
+fn test_build_with_environment_overlay() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("pages")).unwrap();
+    fs::write(
+        root.join("balzac.toml"),
+        "[global]\nanalytics_id = \"dev\"\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("balzac.production.toml"),
+        "[global]\nanalytics_id = \"UA-1\"\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("pages/index.hbs"),
+        "{{environment}} {{analytics_id}}",
+    )
+    .unwrap();
+
+    Builder::from_root(root)
+        .expect("Failed to load config")
+        .build()
+        .expect("Failed to build");
+    assert_eq!(
+        fs::read_to_string(root.join("dist/index.html")).unwrap(),
+        " dev"
+    );
+
+    Builder::from_root_with_environment(root, Some("production"))
+        .expect("Failed to load config")
+        .build()
+        .expect("Failed to build");
+    assert_eq!(
+        fs::read_to_string(root.join("dist/index.html")).unwrap(),
+        "production UA-1"
+    );
+}
+
+#[test]
+fn test_build_report_json() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("pages/posts")).unwrap();
+    fs::create_dir_all(root.join("content/posts")).unwrap();
+    fs::create_dir_all(root.join("content/notes")).unwrap();
+    fs::write(
+        root.join("balzac.toml"),
+        "[hooks]\nbuild_before = \"true ${BALZAC_TEST_UNSET_REPORT_TOKEN:-s3cret}\"\n",
+    )
+    .unwrap();
+    fs::write(root.join("pages/index.hbs"), "<h1>Home</h1>").unwrap();
+    fs::write(root.join("pages/posts/details.hbs"), "{{{content}}}").unwrap();
+    fs::write(root.join("content/posts/a.md"), "---\ntitle: A\n---\nBody").unwrap();
+    fs::write(root.join("content/notes/b.md"), "---\ntitle: B\n---\nBody").unwrap();
+
+    let builder = Builder::from_root(root).expect("Failed to load config");
+    let report = builder.build().expect("Failed to build");
+    let json = balzac::report::to_json(&report);
+
+    assert_eq!(json["total_pages"], 2);
+    assert_eq!(json["rendered_pages"], 2);
+    assert_eq!(json["hooks"][0]["phase"], "build_before");
+    // Expanded hook commands may hold secrets
+    assert!(!json.to_string().contains("s3cret"));
+    assert!(
+        json["phases"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|phase| phase["name"] == "discovery")
+    );
+
+    let index = &json["pages"][0];
+    assert_eq!(index["url"], "/");
+    assert_eq!(index["type"], "static");
+    assert_eq!(index["bytes"], 13);
+    assert!(index["render_time_ms"].is_number());
+
+    let post = &json["pages"][1];
+    assert_eq!(post["url"], "/posts/a");
+    assert_eq!(post["type"], "collection");
+    assert_eq!(post["collection"], "posts");
+
+    assert_eq!(json["warnings"].as_array().unwrap().len(), 1);
+    assert!(
+        json["skipped"][0]["path"]
+            .as_str()
+            .unwrap()
+            .ends_with("content/notes")
+    );
+
+    let report = builder.build().expect("Failed to rebuild");
+    let json = balzac::report::to_json(&report);
+    assert_eq!(json["rendered_pages"], 0);
+    assert_eq!(json["pages"][0]["rendered"], false);
+    assert_eq!(json["pages"][0]["bytes"], 13);
+    assert_eq!(json["unchanged_pages"], 2);
+    assert_eq!(json["skipped"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_drafts_are_only_built_when_enabled() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("pages/posts")).unwrap();
+    fs::create_dir_all(root.join("content/posts")).unwrap();
+    fs::write(
+        root.join("balzac.toml"),
+        "base_url = \"https://example.com\"\n\n[sitemap]\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("pages/posts/details.hbs"),
+        "{{#if draft}}[DRAFT] {{/if}}{{fm.title}}",
+    )
+    .unwrap();
+    fs::write(
+        root.join("content/posts/done.md"),
+        "---\ntitle: Done\n---\nBody",
+    )
+    .unwrap();
+    fs::write(
+        root.join("content/posts/wip.md"),
+        "---\ntitle: WIP\ndraft: true\n---\nBody",
+    )
+    .unwrap();
+
+    let report = Builder::from_root(root)
+        .expect("Failed to load config")
+        .build()
+        .expect("Failed to build");
+    assert_eq!(report.site_pages.all().len(), 1);
+    assert!(!root.join("dist/posts/wip.html").exists());
+    assert_eq!(report.diagnostics.skipped[0].reason, "draft");
+    let sitemap = fs::read_to_string(root.join("dist/sitemap.xml")).unwrap();
+    assert!(sitemap.contains("/posts/done"));
+    assert!(!sitemap.contains("/posts/wip"));
+
+    let report = Builder::from_root(root)
+        .expect("Failed to load config")
+        .drafts(true)
+        .build()
+        .expect("Failed to build with drafts");
+    assert_eq!(report.site_pages.all().len(), 2);
+    assert_eq!(
+        fs::read_to_string(root.join("dist/posts/wip.html")).unwrap(),
+        "[DRAFT] WIP"
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("dist/posts/done.html")).unwrap(),
+        "Done"
+    );
+    // Previews list drafts in their sitemap like any other page
+    let sitemap = fs::read_to_string(root.join("dist/sitemap.xml")).unwrap();
+    assert!(sitemap.contains("/posts/done"));
+    assert!(sitemap.contains("/posts/wip"));
+}
+
+#[test]
+fn test_publication_window() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("pages/posts")).unwrap();
+    fs::create_dir_all(root.join("content/posts")).unwrap();
+    fs::write(
+        root.join("balzac.toml"),
+        "base_url = \"https://example.com\"\n\n[sitemap]\n",
+    )
+    .unwrap();
+    fs::write(root.join("pages/posts/details.hbs"), "{{fm.title}}").unwrap();
+    fs::write(
+        root.join("content/posts/scheduled.md"),
+        "---\ntitle: Scheduled\npublish_date: 2024-06-01T00:00:00Z\n---\nBody",
+    )
+    .unwrap();
+    fs::write(
+        root.join("content/posts/promo.md"),
+        "---\ntitle: Promo\nexpiry_date: 2024-03-01T00:00:00Z\n---\nBody",
+    )
+    .unwrap();
+
+    let build_at = |now: &str| {
+        Builder::from_root(root)
+            .expect("Failed to load config")
+            .now(balzac::date::parse_date(now).unwrap())
+            .build()
+            .expect("Failed to build")
+    };
+
+    let report = build_at("2024-01-01T00:00:00Z");
+    assert_eq!(report.site_pages.all().len(), 1);
+    assert!(root.join("dist/posts/promo.html").exists());
+    assert!(!root.join("dist/posts/scheduled.html").exists());
+    let sitemap = fs::read_to_string(root.join("dist/sitemap.xml")).unwrap();
+    assert!(!sitemap.contains("/posts/scheduled"));
+    assert!(
+        report.diagnostics.skipped[0]
+            .reason
+            .starts_with("scheduled for")
+    );
+
+    let report = build_at("2024-07-01T00:00:00Z");
+    assert_eq!(report.site_pages.all().len(), 1);
+    assert!(!root.join("dist/posts/promo.html").exists());
+    assert!(root.join("dist/posts/scheduled.html").exists());
+    let sitemap = fs::read_to_string(root.join("dist/sitemap.xml")).unwrap();
+    assert!(sitemap.contains("/posts/scheduled"));
+    assert!(!sitemap.contains("/posts/promo"));
+}
+
+#[test]
+fn test_invalid_publish_date_fails_build() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("pages/posts")).unwrap();
+    fs::create_dir_all(root.join("content/posts")).unwrap();
+    fs::write(root.join("balzac.toml"), "").unwrap();
+    fs::write(root.join("pages/posts/details.hbs"), "{{fm.title}}").unwrap();
+    fs::write(
+        root.join("content/posts/a.md"),
+        "---\ntitle: A\npublish_date: someday\n---\nBody",
+    )
+    .unwrap();
+
+    let result = Builder::from_root(root).unwrap().build();
+    assert!(matches!(result, Err(BuildError::Discovery(_))));
+}
+
+#[test]
+fn test_build_syncs_output_directory() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("pages")).unwrap();
+    fs::create_dir_all(root.join("dist/.vite")).unwrap();
+    fs::write(root.join("balzac.toml"), "").unwrap();
+    fs::write(root.join("dist/.vite/manifest.json"), "{}").unwrap();
+    fs::write(root.join("pages/index.hbs"), "<h1>Home</h1>").unwrap();
+    fs::write(root.join("pages/old.hbs"), "<h1>Old</h1>").unwrap();
+
+    let builder = Builder::from_root(root).expect("Failed to load config");
+    builder.build().expect("Failed to build");
+    assert!(root.join("dist/.vite/manifest.json").exists());
+    assert!(root.join("dist/.balzac-manifest.json").exists());
+
+    let old_mtime = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+    fs::File::options()
+        .write(true)
+        .open(root.join("dist/index.html"))
+        .unwrap()
+        .set_modified(old_mtime)
+        .unwrap();
+
+    // Without the build cache every page is rendered again, but unchanged
+    // outputs are not rewritten
+    fs::remove_file(root.join(balzac::cache::CACHE_FILENAME)).unwrap();
+    fs::remove_file(root.join("pages/old.hbs")).unwrap();
+    let report = builder.build().expect("Failed to rebuild");
+
+    assert_eq!(report.rendered_pages, 1);
+    assert_eq!(report.removed_outputs, vec![root.join("./dist/old.html")]);
+    assert!(!root.join("dist/old.html").exists());
+    assert!(root.join("dist/.vite/manifest.json").exists());
+    assert_eq!(
+        fs::metadata(root.join("dist/index.html"))
+            .unwrap()
+            .modified()
+            .unwrap(),
+        old_mtime
+    );
+}
+
+#[test]
+fn test_nested_pages_are_discovered() {
+    let (_temp, temp_path, pages_dir, output_dir, _, _, _, content_dir) = setup_test_project();
+
+    fs::write(pages_dir.join("index.hbs"), "<h1>Home</h1>").expect("Failed to write index");
+    fs::create_dir_all(pages_dir.join("docs").join("guides"))
+        .expect("Failed to create docs pages dir");
+    fs::write(pages_dir.join("docs").join("index.hbs"), "Docs").expect("Failed to write docs");
+    fs::write(pages_dir.join("docs").join("install.hbs"), "Install")
+        .expect("Failed to write install");
+    // Not a collection, so a page like any other
+    fs::write(pages_dir.join("docs").join("list.hbs"), "List").expect("Failed to write list");
+    fs::write(
+        pages_dir.join("docs").join("guides").join("deploy.html"),
+        "Deploy",
+    )
+    .expect("Failed to write deploy");
+    fs::create_dir(pages_dir.join("blog")).expect("Failed to create blog pages dir");
+    fs::write(pages_dir.join("blog").join("details.hbs"), "{{fm.title}}")
+        .expect("Failed to write details template");
+    fs::write(pages_dir.join("blog").join("about.hbs"), "About").expect("Failed to write about");
+    // Templates of a collection without content yet
+    fs::create_dir(pages_dir.join("notes")).expect("Failed to create notes pages dir");
+    fs::write(pages_dir.join("notes").join("details.hbs"), "{{fm.title}}")
+        .expect("Failed to write details template");
+    fs::write(pages_dir.join("notes").join("list.hbs"), "Notes")
+        .expect("Failed to write list template");
+    fs::create_dir_all(content_dir.join("blog")).expect("Failed to create blog content dir");
+    fs::write(
+        content_dir.join("blog").join("post.md"),
+        "---\ntitle: Post\n---\n\nBody",
+    )
+    .expect("Failed to write post");
+
+    let report = Builder::new(Config::default(), &temp_path)
+        .build()
+        .expect("Build should succeed");
+
+    let urls: Vec<&str> = report
+        .site_pages
+        .static_pages()
+        .iter()
+        .map(|page| page.url_path.as_str())
+        .collect();
+    assert_eq!(
+        urls,
+        vec![
+            "/blog/about",
+            "/docs/guides/deploy",
+            "/docs/",
+            "/docs/install",
+            "/docs/list",
+            "/"
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("docs").join("index.html")).unwrap(),
+        "Docs"
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("docs").join("guides").join("deploy.html")).unwrap(),
+        "Deploy"
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("blog").join("post.html")).unwrap(),
+        "Post"
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("blog").join("about.html")).unwrap(),
+        "About"
+    );
+    assert!(!output_dir.join("blog").join("details.html").exists());
+    assert!(!output_dir.join("notes").exists());
+}
+
+#[test]
+fn test_url_style_applies_to_outputs_and_sitemap() {
+    let (_temp, temp_path, pages_dir, output_dir, _, _, _, content_dir) = setup_test_project();
+
+    fs::write(pages_dir.join("index.hbs"), "Home").expect("Failed to write index");
+    fs::write(pages_dir.join("about.hbs"), "About").expect("Failed to write about");
+    fs::create_dir(pages_dir.join("blog")).expect("Failed to create blog pages dir");
+    fs::write(pages_dir.join("blog").join("details.hbs"), "{{fm.title}}")
+        .expect("Failed to write details template");
+    fs::create_dir_all(content_dir.join("blog")).expect("Failed to create blog content dir");
+    fs::write(
+        content_dir.join("blog").join("post.md"),
+        "---\ntitle: Post\n---\n\nBody",
+    )
+    .expect("Failed to write post");
+
+    let config = Config {
+        base_url: Some("https://example.com".to_string()),
+        url_style: UrlStyle::Pretty,
+        ..Default::default()
+    };
+    Builder::new(config, &temp_path)
+        .build()
+        .expect("Build should succeed");
+
+    assert_eq!(
+        fs::read_to_string(output_dir.join("about").join("index.html")).unwrap(),
+        "About"
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("blog").join("post").join("index.html")).unwrap(),
+        "Post"
+    );
+    let sitemap = fs::read_to_string(output_dir.join("sitemap.xml")).unwrap();
+    assert!(sitemap.contains("<loc>https://example.com/</loc>"));
+    assert!(sitemap.contains("<loc>https://example.com/about/</loc>"));
+    assert!(sitemap.contains("<loc>https://example.com/blog/post/</loc>"));
+
+    let config = Config {
+        base_url: Some("https://example.com".to_string()),
+        url_style: UrlStyle::Html,
+        ..Default::default()
+    };
+    Builder::new(config, &temp_path)
+        .build()
+        .expect("Build should succeed");
+
+    assert!(output_dir.join("about.html").exists());
+    assert!(!output_dir.join("about").exists());
+    let sitemap = fs::read_to_string(output_dir.join("sitemap.xml")).unwrap();
+    assert!(sitemap.contains("<loc>https://example.com/about.html</loc>"));
+    assert!(sitemap.contains("<loc>https://example.com/blog/post.html</loc>"));
+}
+
+#[test]
+fn test_assets_are_copied_recursively_to_mount_point() {
+    let (_temp, temp_path, pages_dir, output_dir, _, _, assets_dir, _) = setup_test_project();
+
+    fs::write(pages_dir.join("index.hbs"), "Home").expect("Failed to write index");
+    fs::create_dir_all(assets_dir.join("img")).expect("Failed to create assets dir");
+    fs::write(assets_dir.join("robots.txt"), "User-agent: *").expect("Failed to write robots");
+    fs::write(assets_dir.join("img").join("logo.png"), "logo").expect("Failed to write logo");
+    fs::write(assets_dir.join("img").join("logo.png.map"), "{}").expect("Failed to write map");
+
+    let report = Builder::new(Config::default(), &temp_path)
+        .build()
+        .expect("Build should succeed");
+    assert_eq!(
+        report.assets,
+        vec![
+            output_dir.join("assets").join("img").join("logo.png"),
+            output_dir.join("assets").join("robots.txt"),
+        ]
+    );
+
+    let config = Config {
+        assets_mount: "/".to_string(),
+        ..Default::default()
+    };
+    Builder::new(config, &temp_path)
+        .build()
+        .expect("Build should succeed");
+
+    assert_eq!(
+        fs::read_to_string(output_dir.join("robots.txt")).unwrap(),
+        "User-agent: *"
+    );
+    assert!(output_dir.join("img").join("logo.png").exists());
+    assert!(!output_dir.join("img").join("logo.png.map").exists());
+    assert!(!output_dir.join("assets").exists());
+}
+
+#[test]
+fn test_static_directories_are_copied_to_their_mount_points() {
+    let (_temp, temp_path, pages_dir, output_dir, _, _, _, _) = setup_test_project();
+
+    fs::write(pages_dir.join("index.hbs"), "Home").expect("Failed to write index");
+    let public_dir = temp_path.join("public");
+    fs::create_dir_all(&public_dir).expect("Failed to create public dir");
+    fs::write(public_dir.join("robots.txt"), "User-agent: *").expect("Failed to write robots");
+    let fonts_dir = temp_path.join("node_modules/katex/dist/fonts");
+    fs::create_dir_all(&fonts_dir).expect("Failed to create fonts dir");
+    fs::write(fonts_dir.join("KaTeX_Main.woff2"), "woff2").expect("Failed to write font");
+    fs::write(fonts_dir.join("KaTeX_Main.ttf"), "ttf").expect("Failed to write font");
+    fs::write(fonts_dir.join("KaTeX_Typewriter.woff2"), "woff2").expect("Failed to write font");
+
+    let config: Config = toml::from_str(
+        r#"
+[[static]]
+from = "./public"
+
+[[static]]
+from = "./node_modules/katex/dist/fonts"
+to = "/vendor/katex/fonts"
+include = ["*.woff2"]
+exclude = ["*Typewriter*"]
+"#,
+    )
+    .expect("Failed to parse config");
+
+    let report = Builder::new(config, &temp_path)
+        .build()
+        .expect("Build should succeed");
+
+    assert_eq!(
+        report.assets,
+        vec![
+            output_dir.join("robots.txt"),
+            output_dir.join("vendor/katex/fonts/KaTeX_Main.woff2"),
+        ]
+    );
+
+    let config: Config =
+        toml::from_str("[[static]]\nfrom = \"./missing\"\n").expect("Failed to parse config");
+    let error = Builder::new(config, &temp_path)
+        .build()
+        .expect_err("Build should fail");
+    assert!(matches!(error, BuildError::Asset(_)));
+
+    // A mounted file replacing a rendered page fails the build
+    fs::write(public_dir.join("index.html"), "Public").expect("Failed to write index");
+    let config: Config =
+        toml::from_str("[[static]]\nfrom = \"./public\"\n").expect("Failed to parse config");
+    let error = Builder::new(config, &temp_path)
+        .build()
+        .expect_err("Build should fail");
+    assert!(matches!(error, BuildError::Discovery(_)));
+    let message = error.to_string();
+    assert!(message.contains("index.html is produced by"), "{}", message);
+    assert!(message.contains("public/index.html"), "{}", message);
+    assert_eq!(
+        fs::read_to_string(output_dir.join("index.html")).unwrap(),
+        "Home"
+    );
+}
+
+#[test]
+fn test_nested_collection_sections() {
+    let (_temp, temp_path, pages_dir, output_dir, _, _, _, content_dir) = setup_test_project();
+
+    fs::create_dir(pages_dir.join("docs")).expect("Failed to create docs pages dir");
+    fs::write(
+        pages_dir.join("docs").join("details.hbs"),
+        "{{fm.title}}|{{#each sections}}{{this}};{{/each}}|\
+         {{#each collection.items}}{{url}};{{/each}}|\
+         {{#each collection.sections}}{{name}}:{{#each items}}{{url}};{{/each}}{{/each}}",
+    )
+    .expect("Failed to write details template");
+    let guides_dir = content_dir.join("docs").join("guides");
+    fs::create_dir_all(&guides_dir).expect("Failed to create guides dir");
+    fs::write(
+        content_dir.join("docs").join("intro.md"),
+        "---\ntitle: Intro\n---\n\nIntro",
+    )
+    .expect("Failed to write intro");
+    fs::write(
+        guides_dir.join("setup.md"),
+        "---\ntitle: Setup\n---\n\nSetup",
+    )
+    .expect("Failed to write setup");
+
+    let tree_config = || -> Config {
+        toml::from_str("[collections.docs]\ntree = true\n").expect("Failed to parse config")
+    };
+    let report = Builder::new(tree_config(), &temp_path)
+        .build()
+        .expect("Build should succeed");
+
+    let setup = report
+        .site_pages
+        .collection("docs")
+        .into_iter()
+        .find(|page| page.url_path == "/docs/guides/setup")
+        .expect("Setup should be discovered");
+    assert_eq!(setup.sections, vec!["guides".to_string()]);
+
+    assert_eq!(
+        fs::read_to_string(output_dir.join("docs").join("guides").join("setup.html")).unwrap(),
+        "Setup|guides;|/docs/intro;|guides:/docs/guides/setup;"
+    );
+
+    // Adding an item renders the other items of the collection again
+    fs::write(
+        guides_dir.join("deploy.md"),
+        "---\ntitle: Deploy\n---\n\nDeploy",
+    )
+    .expect("Failed to write deploy");
+    let report = Builder::new(tree_config(), &temp_path)
+        .build()
+        .expect("Build should succeed");
+    assert_eq!(report.rendered_pages, 3);
+    assert_eq!(
+        fs::read_to_string(output_dir.join("docs").join("intro.html")).unwrap(),
+        "Intro||/docs/intro;|guides:/docs/guides/deploy;/docs/guides/setup;"
+    );
+
+    // Without the tree, items only depend on their own file
+    let report = Builder::new(Config::default(), &temp_path)
+        .build()
+        .expect("Build should succeed");
+    assert_eq!(report.rendered_pages, 3);
+    assert_eq!(
+        fs::read_to_string(output_dir.join("docs").join("intro.html")).unwrap(),
+        "Intro|||"
+    );
+    fs::write(
+        guides_dir.join("deploy.md"),
+        "---\ntitle: Deploy to production\n---\n\nDeploy",
+    )
+    .expect("Failed to write deploy");
+    let report = Builder::new(Config::default(), &temp_path)
+        .build()
+        .expect("Build should succeed");
+    assert_eq!(report.rendered_pages, 1);
+}
+
+#[test]
+fn test_collection_settings() {
+    let (_temp, temp_path, pages_dir, output_dir, layouts_dir, _, _, content_dir) =
+        setup_test_project();
+
+    fs::write(pages_dir.join("index.hbs"), "Home").expect("Failed to write index");
+    fs::create_dir_all(&layouts_dir).expect("Failed to create layouts dir");
+    fs::write(layouts_dir.join("post.hbs"), "<h1>{{fm.title}}</h1>")
+        .expect("Failed to write post template");
+    fs::create_dir_all(content_dir.join("posts")).expect("Failed to create posts dir");
+    fs::write(
+        content_dir.join("posts").join("hello.md"),
+        "---\ntitle: Hello\ndate: 2024-03-07\n---\n\nHello",
+    )
+    .expect("Failed to write post");
+    fs::create_dir(pages_dir.join("docs")).expect("Failed to create docs pages dir");
+    fs::write(pages_dir.join("docs").join("details.hbs"), "{{fm.title}}")
+        .expect("Failed to write docs template");
+    fs::create_dir_all(content_dir.join("docs")).expect("Failed to create docs dir");
+    fs::write(
+        content_dir.join("docs").join("intro.md"),
+        "---\ntitle: Intro\n---\n\nIntro",
+    )
+    .expect("Failed to write doc");
+
+    let config: Config = toml::from_str(
+        r#"
+[collections.posts]
+details = "./layouts/post.hbs"
+permalink = "/blog/{year}/{month}/{slug}/"
+
+[collections.docs]
+output = "documentation"
+"#,
+    )
+    .expect("Failed to parse config");
+    let report = Builder::new(config, &temp_path)
+        .build()
+        .expect("Build should succeed");
+
+    let urls: Vec<&str> = report
+        .site_pages
+        .collection_items()
+        .iter()
+        .map(|page| page.url_path.as_str())
+        .collect();
+    assert_eq!(urls, vec!["/documentation/intro", "/blog/2024/03/hello/"]);
+    assert_eq!(
+        fs::read_to_string(output_dir.join("blog/2024/03/hello/index.html")).unwrap(),
+        "<h1>Hello</h1>"
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("documentation/intro.html")).unwrap(),
+        "Intro"
+    );
+
+    let config: Config = toml::from_str(
+        "[collections.posts]\ndetails = \"./layouts/post.hbs\"\npermalink = \"/{author}/{slug}\"\n",
+    )
+    .expect("Failed to parse config");
+    let problems = check::check_config(&config, &temp_path);
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+    assert!(
+        problems[0]
+            .message
+            .contains("missing frontmatter field author")
+    );
+    assert!(Builder::new(config, &temp_path).build().is_err());
+}
+
+#[test]
+fn test_frontmatter_slug_and_url_overrides() {
+    let (_temp, temp_path, pages_dir, output_dir, _, _, _, content_dir) = setup_test_project();
+
+    fs::write(pages_dir.join("index.hbs"), "Home").expect("Failed to write index");
+    fs::create_dir(pages_dir.join("blog")).expect("Failed to create blog pages dir");
+    fs::write(pages_dir.join("blog").join("details.hbs"), "{{fm.title}}")
+        .expect("Failed to write details template");
+    fs::create_dir_all(content_dir.join("blog")).expect("Failed to create blog content dir");
+    fs::write(
+        content_dir.join("blog").join("2024-01-01-hello.md"),
+        "---\ntitle: Hello\nslug: hello\n---\n\nHello",
+    )
+    .expect("Failed to write post");
+    fs::write(
+        content_dir.join("blog").join("about-the-blog.md"),
+        "---\ntitle: About\nurl: /about/\n---\n\nAbout",
+    )
+    .expect("Failed to write post");
+
+    Builder::new(Config::default(), &temp_path)
+        .build()
+        .expect("Build should succeed");
+    assert_eq!(
+        fs::read_to_string(output_dir.join("blog").join("hello.html")).unwrap(),
+        "Hello"
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("about").join("index.html")).unwrap(),
+        "About"
+    );
+
+    // A collection item colliding with a static page fails the build
+    fs::write(
+        content_dir.join("blog").join("home.md"),
+        "---\ntitle: Home\nurl: /\n---\n\nHome",
+    )
+    .expect("Failed to write post");
+    let error = Builder::new(Config::default(), &temp_path)
+        .build()
+        .expect_err("Colliding URLs should fail the build");
+    assert!(error.to_string().contains("URL / is produced by"));
+
+    fs::write(
+        content_dir.join("blog").join("home.md"),
+        "---\ntitle: Hello again\nslug: hello\n---\n\nHello",
+    )
+    .expect("Failed to write post");
+    let error = Builder::new(Config::default(), &temp_path)
+        .build()
+        .expect_err("Colliding slugs should fail the build");
+    let message = error.to_string();
+    assert!(
+        message.contains("URL /blog/hello is produced by"),
+        "{}",
+        message
+    );
+    assert!(message.contains("2024-01-01-hello.md"));
+    assert!(message.contains("home.md"));
+
+    let problems = check::check_config(&Config::default(), &temp_path);
+    assert!(
+        problems
+            .iter()
+            .any(|problem| problem.message.starts_with("URL /blog/hello")),
+        "{:?}",
+        problems
+    );
+}
+
+#[test]
+fn test_paginated_collection_lists() {
+    let (_temp, temp_path, pages_dir, output_dir, _, _, _, content_dir) = setup_test_project();
+
+    fs::create_dir(pages_dir.join("posts")).expect("Failed to create posts pages dir");
+    fs::write(pages_dir.join("posts").join("details.hbs"), "{{fm.title}}")
+        .expect("Failed to write details template");
+    fs::write(
+        pages_dir.join("posts").join("list.hbs"),
+        "{{#each pagination.items}}{{fm.title}};{{/each}}\
+         {{pagination.page}}/{{pagination.total_pages}} prev={{pagination.prev}} next={{pagination.next}}",
+    )
+    .expect("Failed to write list template");
+    fs::create_dir_all(content_dir.join("posts")).expect("Failed to create posts dir");
+    for (name, date) in [
+        ("a", "2024-01-01"),
+        ("b", "2024-03-01"),
+        ("c", "2024-02-01"),
+    ] {
+        fs::write(
+            content_dir.join("posts").join(format!("{}.md", name)),
+            format!("---\ntitle: {}\ndate: {}\n---\n\n{}", name, date, name),
+        )
+        .expect("Failed to write post");
+    }
+    fs::write(
+        content_dir.join("posts").join("undated.md"),
+        "---\ntitle: undated\n---\n\nUndated",
+    )
+    .expect("Failed to write post");
+
+    let config: Config =
+        toml::from_str("[collections.posts]\npaginate = 3\n").expect("Failed to parse config");
+    let report = Builder::new(config, &temp_path)
+        .build()
+        .expect("Build should succeed");
+
+    let urls: Vec<&str> = report
+        .site_pages
+        .collection_lists()
+        .iter()
+        .map(|page| page.url_path.as_str())
+        .collect();
+    assert_eq!(urls, vec!["/posts/", "/posts/page/2/"]);
+    assert!(!output_dir.join("posts/list.html").exists());
+    assert_eq!(
+        fs::read_to_string(output_dir.join("posts/index.html")).unwrap(),
+        "b;c;a;1/2 prev= next=/posts/page/2/"
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("posts/page/2/index.html")).unwrap(),
+        "undated;2/2 prev=/posts/ next="
+    );
+}
+
+#[test]
+fn test_taxonomies() {
+    let (_temp, temp_path, pages_dir, output_dir, _, _, _, content_dir) = setup_test_project();
+
+    fs::create_dir(pages_dir.join("posts")).expect("Failed to create posts pages dir");
+    fs::write(pages_dir.join("posts").join("details.hbs"), "{{fm.title}}")
+        .expect("Failed to write details template");
+    fs::create_dir(pages_dir.join("tags")).expect("Failed to create tags pages dir");
+    fs::write(
+        pages_dir.join("tags").join("list.hbs"),
+        "{{#each terms}}{{name}}={{count}}@{{url}};{{/each}}",
+    )
+    .expect("Failed to write tags list template");
+    fs::write(
+        pages_dir.join("tags").join("term.hbs"),
+        "{{taxonomy}}:{{term.name}}:{{#each term.items}}{{fm.title}};{{/each}}",
+    )
+    .expect("Failed to write tags term template");
+    fs::write(pages_dir.join("tags").join("about.hbs"), "About tags")
+        .expect("Failed to write about");
+    // Not a configured taxonomy, so a page like any other
+    fs::create_dir(pages_dir.join("glossary")).expect("Failed to create glossary pages dir");
+    fs::write(pages_dir.join("glossary").join("term.hbs"), "Term")
+        .expect("Failed to write glossary term");
+    fs::create_dir_all(content_dir.join("posts")).expect("Failed to create posts dir");
+    fs::write(
+        content_dir.join("posts").join("a.md"),
+        "---\ntitle: A\ndate: 2024-01-01\ntags: [Rust, Web Dev]\n---\n\nA",
+    )
+    .expect("Failed to write post");
+    fs::write(
+        content_dir.join("posts").join("b.md"),
+        "---\ntitle: B\ndate: 2024-02-01\ntags: rust\n---\n\nB",
+    )
+    .expect("Failed to write post");
+
+    let config: Config = toml::from_str(
+        "base_url = \"https://example.com\"\n\n[taxonomies.tags]\n\n[sitemap]\nenabled = true\n",
+    )
+    .expect("Failed to parse config");
+    let report = Builder::new(config, &temp_path)
+        .build()
+        .expect("Build should succeed");
+
+    let urls: Vec<&str> = report
+        .site_pages
+        .taxonomy_pages()
+        .iter()
+        .map(|page| page.url_path.as_str())
+        .collect();
+    assert_eq!(urls, vec!["/tags/", "/tags/rust/", "/tags/web-dev/"]);
+    assert_eq!(
+        fs::read_to_string(output_dir.join("tags/index.html")).unwrap(),
+        "Rust=2@/tags/rust/;Web Dev=1@/tags/web-dev/;"
+    );
+    assert_eq!(
+        fs::read_to_string(output_dir.join("tags/rust/index.html")).unwrap(),
+        "tags:Rust:B;A;"
+    );
+    let sitemap =
+        fs::read_to_string(output_dir.join("sitemap.xml")).expect("Failed to read sitemap");
+    assert!(sitemap.contains("<loc>https://example.com/tags/</loc>"));
+    assert!(sitemap.contains("<loc>https://example.com/tags/web-dev/</loc>"));
+
+    let urls: Vec<&str> = report
+        .site_pages
+        .static_pages()
+        .iter()
+        .map(|page| page.url_path.as_str())
+        .collect();
+    assert_eq!(urls, vec!["/glossary/term", "/tags/about"]);
+    assert_eq!(
+        fs::read_to_string(output_dir.join("tags/about.html")).unwrap(),
+        "About tags"
+    );
+
+    fs::write(
+        content_dir.join("posts").join("c.md"),
+        "---\ntitle: C\ntags: 3\n---\n\nC",
+    )
+    .expect("Failed to write post");
+    let config: Config = toml::from_str("[taxonomies.tags]\n").expect("Failed to parse config");
+    let problems = check::check_config(&config, &temp_path);
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+    assert!(problems[0].message.contains("invalid tags 3"));
+    assert!(Builder::new(config, &temp_path).build().is_err());
+}